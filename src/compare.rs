@@ -0,0 +1,184 @@
+use std::cmp;
+use Shape;
+use Variable;
+
+/// Default relative tolerance of `assert_tensor_close!` and `assert_node_close!`.
+pub const DEFAULT_RTOL: f32 = 1e-5;
+/// Default absolute tolerance of `assert_tensor_close!` and `assert_node_close!`.
+pub const DEFAULT_ATOL: f32 = 1e-8;
+
+/// Returns whether `a` is close to `b` in the same manner as `numpy.isclose`.
+fn is_close(a: f32, b: f32, rtol: f32, atol: f32) -> bool {
+    a == b || (a - b).abs() <= atol + rtol * b.abs()
+}
+
+/// Returns the offset of the first pair of values which are not close.
+pub(crate) fn first_mismatch(a: &[f32], b: &[f32], rtol: f32, atol: f32) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(&x, &y)| !is_close(x, y, rtol, atol))
+}
+
+/// Returns the maximum absolute difference between two lists of values.
+pub(crate) fn max_abs_diff(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| (x - y).abs())
+        .fold(0.0, |acc, d| if d > acc || d.is_nan() { d } else { acc })
+}
+
+/// Converts an offset in the column-major values into the indices and the batch index.
+fn position(shape: &Shape, offset: usize) -> (Vec<u32>, u32) {
+    let volume = shape.volume() as usize;
+    let mut rest = offset % volume;
+    let indices = shape
+        .dims()
+        .iter()
+        .map(|&d| {
+            let i = rest % d as usize;
+            rest /= d as usize;
+            i as u32
+        })
+        .collect();
+    (indices, (offset / volume) as u32)
+}
+
+#[doc(hidden)]
+pub fn assert_close<V: Variable>(
+    left: &V,
+    right: &V,
+    rtol: f32,
+    atol: f32,
+    left_expr: &str,
+    right_expr: &str,
+) {
+    let (left_shape, right_shape) = (left.shape(), right.shape());
+    if left_shape != right_shape {
+        panic!(
+            "assertion failed: `{}` is close to `{}`: shapes differ: {} vs {}",
+            left_expr, right_expr, left_shape, right_shape
+        );
+    }
+    let (left_values, right_values) = (left.to_vector(), right.to_vector());
+    if let Some(offset) = first_mismatch(&left_values, &right_values, rtol, atol) {
+        let (indices, batch) = position(&left_shape, offset);
+        panic!(
+            "assertion failed: `{}` is close to `{}` (rtol: {}, atol: {})\n  shape: {}\n  first mismatch at \
+             index {:?} of batch {}: {} vs {}\n  max abs diff: {}",
+            left_expr,
+            right_expr,
+            rtol,
+            atol,
+            left_shape,
+            indices,
+            batch,
+            left_values[offset],
+            right_values[offset],
+            max_abs_diff(&left_values, &right_values)
+        );
+    }
+}
+
+#[doc(hidden)]
+pub fn assert_shape<V: Variable>(x: &V, dims: &[u32], batch: Option<u32>, expr: &str) {
+    let shape = x.shape();
+    let expected = Shape::from_dims(dims, batch.unwrap_or_else(|| shape.batch()));
+    if shape != expected {
+        let names = if shape.has_names() {
+            let depth = cmp::max(shape.depth() as usize, dims.len()) as u32;
+            let names = (0..depth)
+                .map(|i| shape.name(i).unwrap_or("_"))
+                .collect::<Vec<_>>();
+            format!(" (dimension names: [{}])", names.join(","))
+        } else {
+            String::new()
+        };
+        panic!(
+            "assertion failed: shape of `{}` is {}{}, expected {}",
+            expr, shape, names, expected
+        );
+    }
+}
+
+/// Asserts that two tensors have the same shape and element-wise close values.
+///
+/// The tolerances are `rtol = 1e-5` and `atol = 1e-8` unless specified.
+/// On failure, the shape and the indices of the first differing values are reported.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_tensor_close!(x, y);
+/// assert_tensor_close!(x, y, 1e-3, 1e-6);
+/// ```
+#[macro_export]
+macro_rules! assert_tensor_close {
+    ($left:expr, $right:expr) => {
+        $crate::__assert_close::<$crate::Tensor>(
+            &$left,
+            &$right,
+            $crate::__DEFAULT_RTOL,
+            $crate::__DEFAULT_ATOL,
+            stringify!($left),
+            stringify!($right),
+        )
+    };
+    ($left:expr, $right:expr, $rtol:expr, $atol:expr) => {
+        $crate::__assert_close::<$crate::Tensor>(
+            &$left,
+            &$right,
+            $rtol,
+            $atol,
+            stringify!($left),
+            stringify!($right),
+        )
+    };
+}
+
+/// Asserts that two nodes have the same shape and element-wise close values.
+///
+/// This is the same as `assert_tensor_close!` except that the values of the nodes are calculated.
+#[macro_export]
+macro_rules! assert_node_close {
+    ($left:expr, $right:expr) => {
+        $crate::__assert_close::<$crate::Node>(
+            &$left,
+            &$right,
+            $crate::__DEFAULT_RTOL,
+            $crate::__DEFAULT_ATOL,
+            stringify!($left),
+            stringify!($right),
+        )
+    };
+    ($left:expr, $right:expr, $rtol:expr, $atol:expr) => {
+        $crate::__assert_close::<$crate::Node>(
+            &$left,
+            &$right,
+            $rtol,
+            $atol,
+            stringify!($left),
+            stringify!($right),
+        )
+    };
+}
+
+/// Asserts the dimensions of a node or a tensor.
+///
+/// The batch size is checked only when it is given as the third argument. If the variable has
+/// names of the dimensions, they are also shown on failure.
+///
+/// # Examples
+///
+/// ```ignore
+/// assert_shape!(h, [hidden_size]);
+/// assert_shape!(y, [vocab_size, seq_len], batch_size);
+/// ```
+#[macro_export]
+macro_rules! assert_shape {
+    ($x:expr, [$($dim:expr),* $(,)*]) => {
+        $crate::__assert_shape(&$x, &[$($dim),*], None, stringify!($x))
+    };
+    ($x:expr, [$($dim:expr),* $(,)*], $batch:expr) => {
+        $crate::__assert_shape(&$x, &[$($dim),*], Some($batch), stringify!($x))
+    };
+}
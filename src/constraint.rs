@@ -0,0 +1,10 @@
+use Tensor;
+
+/// `Constraint` trait
+///
+/// Constraints are attached by `Parameter::add_constraint()` or `Model::constrain()`, and
+/// applied to the parameter values by `Optimizer::update()` right after each update.
+pub trait Constraint: Send {
+    /// Projects a value onto the constraint in place.
+    fn apply(&self, x: &mut Tensor);
+}
@@ -0,0 +1,114 @@
+use functions::tensor_funcs as F;
+use Constraint;
+use Tensor;
+
+/// Lower bound of the norms used as divisors.
+const MIN_NORM: f32 = 1e-12;
+
+/// Returns `max(norm, lower)` of the L2 norms of the slices of `x` along `dim`.
+///
+/// The maximum is taken on the host, since the core library has no elementwise maximum. The
+/// norms are much fewer than the values of `x`.
+fn norms(x: &Tensor, dim: u32, lower: f32) -> Tensor {
+    let mut norm = F::sqrt(F::sum(x * x, dim));
+    let values = norm
+        .to_vector()
+        .into_iter()
+        .map(|n| if n < lower { lower } else { n })
+        .collect::<Vec<_>>();
+    norm.reset_by_slice(&values);
+    norm
+}
+
+/// Constraint to rescale slices whose L2 norms exceed a threshold.
+///
+/// The norms are computed along `dim`, e.g. `dim = 0` constrains each column of an embedding
+/// matrix used with `pick(x, ids, 1)`.
+#[derive(Clone, Debug)]
+pub struct MaxNorm {
+    max_norm: f32,
+    dim: u32,
+}
+
+impl MaxNorm {
+    /// Creates a new MaxNorm constraint.
+    pub fn new(max_norm: f32, dim: u32) -> Self {
+        assert!(max_norm > 0.0, "max_norm must be positive: {}", max_norm);
+        MaxNorm { max_norm, dim }
+    }
+}
+
+impl Constraint for MaxNorm {
+    fn apply(&self, x: &mut Tensor) {
+        let scale = F::divide_tensor(self.max_norm, norms(x, self.dim, self.max_norm));
+        let size = x.shape()[self.dim];
+        x.inplace_multiply(&F::broadcast(scale, self.dim, size));
+    }
+}
+
+/// Constraint to replace negative values with 0.
+#[derive(Clone, Debug, Default)]
+pub struct NonNegative;
+
+impl NonNegative {
+    /// Creates a new NonNegative constraint.
+    pub fn new() -> Self {
+        NonNegative
+    }
+}
+
+impl Constraint for NonNegative {
+    fn apply(&self, x: &mut Tensor) {
+        let y = F::relu(&*x);
+        x.reset_by_tensor(&y);
+    }
+}
+
+/// Constraint to clamp values into the range `[lower, upper]`.
+#[derive(Clone, Debug)]
+pub struct ClipByValue {
+    lower: f32,
+    upper: f32,
+}
+
+impl ClipByValue {
+    /// Creates a new ClipByValue constraint.
+    pub fn new(lower: f32, upper: f32) -> Self {
+        assert!(
+            lower <= upper,
+            "lower bound {} must not be greater than upper bound {}",
+            lower,
+            upper
+        );
+        ClipByValue { lower, upper }
+    }
+}
+
+impl Constraint for ClipByValue {
+    fn apply(&self, x: &mut Tensor) {
+        x.inplace_clamp(self.lower, self.upper);
+    }
+}
+
+/// Constraint to normalize slices to have L2 norms of 1.
+///
+/// The norms are computed along `dim` as `MaxNorm`. Slices of zeros are kept as they are.
+#[derive(Clone, Debug)]
+pub struct UnitNorm {
+    dim: u32,
+}
+
+impl UnitNorm {
+    /// Creates a new UnitNorm constraint.
+    pub fn new(dim: u32) -> Self {
+        UnitNorm { dim }
+    }
+}
+
+impl Constraint for UnitNorm {
+    fn apply(&self, x: &mut Tensor) {
+        let norm = norms(x, self.dim, MIN_NORM);
+        let size = x.shape()[self.dim];
+        x.inplace_divide(&F::broadcast(norm, self.dim, size));
+    }
+}
@@ -0,0 +1,2 @@
+mod constraint_impl;
+pub use self::constraint_impl::*;
@@ -1,18 +1,81 @@
 use primitiv_sys as _primitiv;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use ApiResult;
 use Wrap;
 
+/// Kind of the backend of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DeviceKind {
+    /// `devices::Naive`.
+    Naive,
+    /// `devices::Eigen`.
+    Eigen,
+    /// `devices::CUDA`.
+    CUDA,
+    /// `devices::OpenCL`.
+    OpenCL,
+    /// A device which was not created through this crate.
+    Unknown,
+}
+
+lazy_static! {
+    // The core API does not tell the kind of a device, so the constructors record it here.
+    // `Display` of `Tensor` and `Node` shows the kind of their device through this table.
+    static ref DEVICE_KINDS: Mutex<HashMap<usize, DeviceKind>> = Mutex::new(HashMap::new());
+}
+
+pub(crate) fn register_kind(device: *const _primitiv::primitivDevice_t, kind: DeviceKind) {
+    DEVICE_KINDS.lock().unwrap().insert(device as usize, kind);
+}
+
+pub(crate) fn unregister_kind(device: *const _primitiv::primitivDevice_t) {
+    DEVICE_KINDS.lock().unwrap().remove(&(device as usize));
+}
+
 /// `Device` trait
-pub trait Device: Wrap<_primitiv::primitivDevice_t> {}
+pub trait Device: Wrap<_primitiv::primitivDevice_t> {
+    /// Returns the kind of the backend.
+    ///
+    /// This is shown by `Display` of `Tensor` and `Node`. Devices which were not created through
+    /// this crate, e.g. the default device set by another library, are regarded as `Unknown`.
+    fn kind(&self) -> DeviceKind {
+        DEVICE_KINDS
+            .lock()
+            .unwrap()
+            .get(&(self.as_ptr() as usize))
+            .cloned()
+            .unwrap_or(DeviceKind::Unknown)
+    }
+}
 
 macro_rules! impl_device {
     ($name:ident) => {
         impl_wrap!($name, primitivDevice_t);
-        impl_drop!($name, primitivDeleteDevice);
         impl Device for $name {}
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                if self.is_owned() {
+                    ::device::unregister_kind(self.as_ptr());
+                    unsafe {
+                        check_api_status!(_primitiv::primitivDeleteDevice(self.as_mut_ptr()));
+                    }
+                }
+            }
+        }
     };
 }
 
+/// Checks whether the default device is set or not.
+#[cfg(feature = "serialize")]
+pub(crate) fn has_default() -> bool {
+    unsafe {
+        let mut device_ptr: *mut _primitiv::primitivDevice_t = ::std::ptr::null_mut();
+        Result::from_api_status(_primitiv::primitivGetDefaultDevice(&mut device_ptr), ()).is_ok()
+    }
+}
+
 #[allow(dead_code)]
 pub fn set_default<D: Device + ?Sized>(device: &mut D) {
     unsafe {
@@ -1,4 +1,4 @@
-use device::Device;
+use device::{register_kind, Device, DeviceKind};
 use primitiv_sys as _primitiv;
 use std::ptr::{self, NonNull};
 use ApiResult;
@@ -22,6 +22,7 @@ impl CUDA {
                 device_id,
                 &mut device_ptr,
             ));
+            register_kind(device_ptr, DeviceKind::CUDA);
             CUDA::from_raw(device_ptr, true)
         }
     }
@@ -35,6 +36,7 @@ impl CUDA {
                 rng_seed,
                 &mut device_ptr,
             ));
+            register_kind(device_ptr, DeviceKind::CUDA);
             CUDA::from_raw(device_ptr, true)
         }
     }
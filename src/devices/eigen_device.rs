@@ -1,4 +1,4 @@
-use device::Device;
+use device::{register_kind, Device, DeviceKind};
 use primitiv_sys as _primitiv;
 use std::ptr::{self, NonNull};
 use ApiResult;
@@ -19,6 +19,7 @@ impl Eigen {
         unsafe {
             let mut device_ptr: *mut _primitiv::primitivDevice_t = ptr::null_mut();
             check_api_status!(_primitiv::primitivCreateEigenDevice(&mut device_ptr));
+            register_kind(device_ptr, DeviceKind::Eigen);
             Eigen::from_raw(device_ptr, true)
         }
     }
@@ -31,6 +32,7 @@ impl Eigen {
                 rng_seed,
                 &mut device_ptr,
             ));
+            register_kind(device_ptr, DeviceKind::Eigen);
             Eigen::from_raw(device_ptr, true)
         }
     }
@@ -1,4 +1,4 @@
-pub use super::device::set_default;
+pub use super::device::{set_default, DeviceKind};
 
 mod any_device {
     use device::Device;
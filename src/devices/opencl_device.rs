@@ -1,4 +1,4 @@
-use device::Device;
+use device::{register_kind, Device, DeviceKind};
 use primitiv_sys as _primitiv;
 use std::ptr::{self, NonNull};
 use ApiResult;
@@ -23,6 +23,7 @@ impl OpenCL {
                 device_id,
                 &mut device_ptr,
             ));
+            register_kind(device_ptr, DeviceKind::OpenCL);
             OpenCL::from_raw(device_ptr, true)
         }
     }
@@ -36,6 +37,7 @@ impl OpenCL {
                 rng_seed,
                 &mut device_ptr,
             ));
+            register_kind(device_ptr, DeviceKind::OpenCL);
             OpenCL::from_raw(device_ptr, true)
         }
     }
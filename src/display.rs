@@ -0,0 +1,93 @@
+use std::fmt;
+use Shape;
+
+/// Total number of values above which the values are summarized.
+const SUMMARIZE_THRESHOLD: usize = 1000;
+/// Number of items shown at each edge of a summarized axis.
+const EDGE_ITEMS: usize = 3;
+
+/// Writes values laid out by dimensions.
+///
+/// The outermost axis is the minibatch (only if the shape has a minibatch), followed by the
+/// higher dimensions in descending order. The innermost two axes are the first (rows) and the
+/// second (columns) dimensions, so a matrix is printed as it is written mathematically.
+pub(crate) fn write_values(f: &mut fmt::Formatter, shape: &Shape, values: &[f32]) -> fmt::Result {
+    let dims = shape.dims();
+    // (size, stride) of each axis from the outermost one.
+    let mut axes = vec![];
+    if shape.has_batch() {
+        axes.push((shape.batch() as usize, shape.volume() as usize));
+    }
+    let mut strides = vec![1usize; dims.len()];
+    for i in 1..dims.len() {
+        strides[i] = strides[i - 1] * dims[i - 1] as usize;
+    }
+    for i in (2..dims.len()).rev() {
+        axes.push((dims[i] as usize, strides[i]));
+    }
+    for i in 0..dims.len().min(2) {
+        axes.push((dims[i] as usize, strides[i]));
+    }
+    let summarize = values.len() > SUMMARIZE_THRESHOLD;
+    let width = values
+        .iter()
+        .map(|v| format!("{}", v).len())
+        .max()
+        .unwrap_or(0);
+    let printer = Printer {
+        values,
+        summarize,
+        width,
+    };
+    printer.write_axis(f, &axes, 0, 0)
+}
+
+struct Printer<'a> {
+    values: &'a [f32],
+    summarize: bool,
+    width: usize,
+}
+
+impl<'a> Printer<'a> {
+    fn write_axis(
+        &self,
+        f: &mut fmt::Formatter,
+        axes: &[(usize, usize)],
+        offset: usize,
+        indent: usize,
+    ) -> fmt::Result {
+        if axes.is_empty() {
+            return write!(f, "{:>width$}", self.values[offset], width = self.width);
+        }
+        let (size, stride) = axes[0];
+        let separator = if axes.len() == 1 {
+            ", ".to_string()
+        } else {
+            format!(",\n{}", " ".repeat(indent + 1))
+        };
+        f.write_str("[")?;
+        for (n, index) in self.indices(size).into_iter().enumerate() {
+            if n > 0 {
+                f.write_str(&separator)?;
+            }
+            match index {
+                Some(i) => self.write_axis(f, &axes[1..], offset + i * stride, indent + 1)?,
+                None => f.write_str("...")?,
+            }
+        }
+        f.write_str("]")
+    }
+
+    /// Returns the indices to show, where `None` represents an elision.
+    fn indices(&self, size: usize) -> Vec<Option<usize>> {
+        if self.summarize && size > 2 * EDGE_ITEMS {
+            (0..EDGE_ITEMS)
+                .map(Some)
+                .chain(Some(None))
+                .chain((size - EDGE_ITEMS..size).map(Some))
+                .collect()
+        } else {
+            (0..size).map(Some).collect()
+        }
+    }
+}
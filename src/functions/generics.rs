@@ -1,4 +1,7 @@
+use compare;
 use devices::AnyDevice;
+#[cfg(feature = "ndarray")]
+use ndarray::ArrayD;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::ops;
@@ -14,15 +17,18 @@ use super::tensor_funcs;
 pub mod exports {
     pub use super::Variable;
     pub use super::{
-        abs, add, add_const, add_var, batch, broadcast, concat, constant, constant_on, conv2d,
-        copy, copy_on, cos, divide, divide_const, divide_var, dropout, elu, exp, flatten, identity,
-        identity_on, input, input_on, log, log_softmax, logsumexp, lrelu, matmul, max, max_pool2d,
-        mean, mean_vars, min, multiply, multiply_const, multiply_var, negative, ones, ones_on,
-        parameter, pick, positive, pow, pow_const, pow_var, pown, prelu, random, relu, reshape,
-        selu, sigmoid, sin, slice, softmax, softmax_cross_entropy, softmax_cross_entropy_with_ids,
-        softplus, split, sqrt, stop_gradient, subtract, subtract_const, subtract_var, sum,
+        abs, add, add_const, add_var, batch, broadcast, concat, concat_named, constant,
+        constant_on, conv2d, copy, copy_on, cos, divide, divide_const, divide_var, dropout, elu,
+        exp, flatten, identity, identity_on, input, input_batch, input_batch_on, input_on, log,
+        log_softmax, logsumexp, lrelu, matmul, max, max_pool2d, mean, mean_vars, min, multiply,
+        multiply_const, multiply_var, negative, ones, ones_on, parameter, pick, pick_named,
+        positive, pow, pow_const, pow_var, pown, prelu, random, relu, reshape, selu, sigmoid, sin,
+        slice, softmax, softmax_cross_entropy, softmax_cross_entropy_with_ids, softplus, split,
+        split_named, sqrt, stop_gradient, subtract, subtract_const, subtract_var, sum, sum_named,
         sum_vars, tan, tanh, transpose, zeros, zeros_on,
     };
+    #[cfg(feature = "ndarray")]
+    pub use super::{input_ndarray, input_ndarray_on};
 }
 pub trait Variable:
     Clone
@@ -99,6 +105,31 @@ pub trait Variable:
     fn argmax(&self, dim: u32) -> Vec<u32>;
 
     fn argmin(&self, dim: u32) -> Vec<u32>;
+
+    /// Returns whether two variables have the same shape and element-wise close values.
+    ///
+    /// Each pair of values `a` and `b` is regarded as close if `|a - b| <= atol + rtol * |b|`,
+    /// which is the same as `numpy.allclose`.
+    fn allclose<T: AsRef<Self>>(&self, other: T, rtol: f32, atol: f32) -> bool {
+        let other = other.as_ref();
+        self.shape() == other.shape()
+            && compare::first_mismatch(&self.to_vector(), &other.to_vector(), rtol, atol).is_none()
+    }
+
+    /// Returns the maximum absolute difference between the values of two variables.
+    ///
+    /// Panics if the shapes of the variables differ.
+    fn max_abs_diff<T: AsRef<Self>>(&self, other: T) -> f32 {
+        let other = other.as_ref();
+        let (shape, other_shape) = (self.shape(), other.shape());
+        assert!(
+            shape == other_shape,
+            "shapes differ: {} vs {}",
+            shape,
+            other_shape
+        );
+        compare::max_abs_diff(&self.to_vector(), &other.to_vector())
+    }
 }
 
 impl Variable for Node {
@@ -249,6 +280,32 @@ pub fn input_on<S: Into<Shape>, D: Device, V: Variable>(
     <V as Variable>::F::input_on(shape, data, dev)
 }
 
+pub fn input_batch<TS: AsRef<[f32]>, S: Into<Shape>, V: Variable>(samples: &[TS], shape: S) -> V {
+    <V as Variable>::F::input_batch(samples, shape)
+}
+
+pub fn input_batch_on<TS: AsRef<[f32]>, S: Into<Shape>, D: Device, V: Variable>(
+    samples: &[TS],
+    shape: S,
+    dev: Option<&mut D>,
+) -> V {
+    <V as Variable>::F::input_batch_on(samples, shape, dev)
+}
+
+#[cfg(feature = "ndarray")]
+pub fn input_ndarray<V: Variable>(array: ArrayD<f32>, batch_axis: Option<usize>) -> V {
+    <V as Variable>::F::input_ndarray(array, batch_axis)
+}
+
+#[cfg(feature = "ndarray")]
+pub fn input_ndarray_on<D: Device, V: Variable>(
+    array: ArrayD<f32>,
+    batch_axis: Option<usize>,
+    dev: Option<&mut D>,
+) -> V {
+    <V as Variable>::F::input_ndarray_on(array, batch_axis, dev)
+}
+
 pub fn parameter<V: Variable>(param: &mut Parameter) -> V {
     <V as Variable>::F::parameter(param)
 }
@@ -265,6 +322,10 @@ pub fn pick<T: AsRef<V>, V: Variable>(x: T, ids: &[u32], dim: u32) -> V {
     <V as Variable>::F::pick(x, ids, dim)
 }
 
+pub fn pick_named<T: AsRef<V>, V: Variable>(x: T, ids: &[u32], name: &str) -> V {
+    <V as Variable>::F::pick_named(x, ids, name)
+}
+
 pub fn slice<T: AsRef<V>, V: Variable>(x: T, dim: u32, lower: u32, upper: u32) -> V {
     <V as Variable>::F::slice(x, dim, lower, upper)
 }
@@ -273,10 +334,18 @@ pub fn split<T: AsRef<V>, V: Variable>(x: T, dim: u32, n: u32) -> Vec<V> {
     <V as Variable>::F::split(x, dim, n)
 }
 
+pub fn split_named<T: AsRef<V>, V: Variable>(x: T, name: &str, n: u32) -> Vec<V> {
+    <V as Variable>::F::split_named(x, name, n)
+}
+
 pub fn concat<TS: AsRef<[T]>, T: AsRef<V>, V: Variable>(xs: TS, dim: u32) -> V {
     <V as Variable>::F::concat(xs, dim)
 }
 
+pub fn concat_named<TS: AsRef<[T]>, T: AsRef<V>, V: Variable>(xs: TS, name: &str) -> V {
+    <V as Variable>::F::concat_named(xs, name)
+}
+
 pub fn reshape<T: AsRef<V>, S: Into<Shape>, V: Variable>(x: T, new_shape: S) -> V {
     <V as Variable>::F::reshape(x, new_shape)
 }
@@ -365,6 +434,10 @@ pub fn sum<T: AsRef<V>, V: Variable>(x: T, dim: u32) -> V {
     <V as Variable>::F::sum(x, dim)
 }
 
+pub fn sum_named<T: AsRef<V>, V: Variable>(x: T, name: &str) -> V {
+    <V as Variable>::F::sum_named(x, name)
+}
+
 pub fn sum_vars<TS: AsRef<[T]>, T: AsRef<V>, V: Variable>(xs: TS) -> V {
     <V as Variable>::F::sum_vars(xs)
 }
@@ -597,13 +670,30 @@ pub trait Functions<Var> {
     fn pown<T: AsRef<Var>>(x: T, k: i32) -> Var;
     fn input<S: Into<Shape>>(shape: S, data: &[f32]) -> Var;
     fn input_on<S: Into<Shape>, D: Device>(shape: S, data: &[f32], dev: Option<&mut D>) -> Var;
+    fn input_batch<TS: AsRef<[f32]>, S: Into<Shape>>(samples: &[TS], shape: S) -> Var;
+    fn input_batch_on<TS: AsRef<[f32]>, S: Into<Shape>, D: Device>(
+        samples: &[TS],
+        shape: S,
+        dev: Option<&mut D>,
+    ) -> Var;
+    #[cfg(feature = "ndarray")]
+    fn input_ndarray(array: ArrayD<f32>, batch_axis: Option<usize>) -> Var;
+    #[cfg(feature = "ndarray")]
+    fn input_ndarray_on<D: Device>(
+        array: ArrayD<f32>,
+        batch_axis: Option<usize>,
+        dev: Option<&mut D>,
+    ) -> Var;
     fn parameter(param: &mut Parameter) -> Var;
     fn copy<T: AsRef<Var>>(x: T) -> Var;
     fn copy_on<T: AsRef<Var>, D: Device>(x: T, dev: Option<&mut D>) -> Var;
     fn pick<T: AsRef<Var>>(x: T, ids: &[u32], dim: u32) -> Var;
+    fn pick_named<T: AsRef<Var>>(x: T, ids: &[u32], name: &str) -> Var;
     fn slice<T: AsRef<Var>>(x: T, dim: u32, lower: u32, upper: u32) -> Var;
     fn split<T: AsRef<Var>>(x: T, dim: u32, n: u32) -> Vec<Var>;
+    fn split_named<T: AsRef<Var>>(x: T, name: &str, n: u32) -> Vec<Var>;
     fn concat<TS: AsRef<[T]>, T: AsRef<Var>>(xs: TS, dim: u32) -> Var;
+    fn concat_named<TS: AsRef<[T]>, T: AsRef<Var>>(xs: TS, name: &str) -> Var;
     fn reshape<T: AsRef<Var>, S: Into<Shape>>(x: T, new_shape: S) -> Var;
     fn flatten<T: AsRef<Var>>(x: T) -> Var;
     fn transpose<T: AsRef<Var>>(x: T) -> Var;
@@ -626,6 +716,7 @@ pub trait Functions<Var> {
     fn max<T: AsRef<Var>>(x: T, dim: u32) -> Var;
     fn min<T: AsRef<Var>>(x: T, dim: u32) -> Var;
     fn sum<T: AsRef<Var>>(x: T, dim: u32) -> Var;
+    fn sum_named<T: AsRef<Var>>(x: T, name: &str) -> Var;
     fn sum_vars<TS: AsRef<[T]>, T: AsRef<Var>>(xs: TS) -> Var;
     fn mean<T: AsRef<Var>>(x: T, dim: u32) -> Var;
     fn mean_vars<TS: AsRef<[T]>, T: AsRef<Var>>(xs: TS) -> Var;
@@ -810,6 +901,36 @@ impl Functions<Node> for FuncImpls<Node> {
         node_funcs::input_on(shape, data, dev)
     }
 
+    #[inline]
+    fn input_batch<TS: AsRef<[f32]>, S: Into<Shape>>(samples: &[TS], shape: S) -> Node {
+        node_funcs::input_batch(samples, shape)
+    }
+
+    #[inline]
+    fn input_batch_on<TS: AsRef<[f32]>, S: Into<Shape>, D: Device>(
+        samples: &[TS],
+        shape: S,
+        dev: Option<&mut D>,
+    ) -> Node {
+        node_funcs::input_batch_on(samples, shape, dev)
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[inline]
+    fn input_ndarray(array: ArrayD<f32>, batch_axis: Option<usize>) -> Node {
+        node_funcs::input_ndarray(array, batch_axis)
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[inline]
+    fn input_ndarray_on<D: Device>(
+        array: ArrayD<f32>,
+        batch_axis: Option<usize>,
+        dev: Option<&mut D>,
+    ) -> Node {
+        node_funcs::input_ndarray_on(array, batch_axis, dev)
+    }
+
     #[inline]
     fn copy<T: AsRef<Node>>(x: T) -> Node {
         node_funcs::copy(x)
@@ -830,6 +951,11 @@ impl Functions<Node> for FuncImpls<Node> {
         node_funcs::pick(x, ids, dim)
     }
 
+    #[inline]
+    fn pick_named<T: AsRef<Node>>(x: T, ids: &[u32], name: &str) -> Node {
+        node_funcs::pick_named(x, ids, name)
+    }
+
     #[inline]
     fn slice<T: AsRef<Node>>(x: T, dim: u32, lower: u32, upper: u32) -> Node {
         node_funcs::slice(x, dim, lower, upper)
@@ -840,11 +966,21 @@ impl Functions<Node> for FuncImpls<Node> {
         node_funcs::split(x, dim, n)
     }
 
+    #[inline]
+    fn split_named<T: AsRef<Node>>(x: T, name: &str, n: u32) -> Vec<Node> {
+        node_funcs::split_named(x, name, n)
+    }
+
     #[inline]
     fn concat<TS: AsRef<[T]>, T: AsRef<Node>>(xs: TS, dim: u32) -> Node {
         node_funcs::concat(xs, dim)
     }
 
+    #[inline]
+    fn concat_named<TS: AsRef<[T]>, T: AsRef<Node>>(xs: TS, name: &str) -> Node {
+        node_funcs::concat_named(xs, name)
+    }
+
     #[inline]
     fn reshape<T: AsRef<Node>, S: Into<Shape>>(x: T, new_shape: S) -> Node {
         node_funcs::reshape(x, new_shape)
@@ -955,6 +1091,11 @@ impl Functions<Node> for FuncImpls<Node> {
         node_funcs::sum(x, dim)
     }
 
+    #[inline]
+    fn sum_named<T: AsRef<Node>>(x: T, name: &str) -> Node {
+        node_funcs::sum_named(x, name)
+    }
+
     #[inline]
     fn sum_vars<TS: AsRef<[T]>, T: AsRef<Node>>(xs: TS) -> Node {
         node_funcs::sum_nodes(xs)
@@ -1290,6 +1431,36 @@ impl Functions<Tensor> for FuncImpls<Tensor> {
         tensor_funcs::input_on(shape, data, dev)
     }
 
+    #[inline]
+    fn input_batch<TS: AsRef<[f32]>, S: Into<Shape>>(samples: &[TS], shape: S) -> Tensor {
+        tensor_funcs::input_batch(samples, shape)
+    }
+
+    #[inline]
+    fn input_batch_on<TS: AsRef<[f32]>, S: Into<Shape>, D: Device>(
+        samples: &[TS],
+        shape: S,
+        dev: Option<&mut D>,
+    ) -> Tensor {
+        tensor_funcs::input_batch_on(samples, shape, dev)
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[inline]
+    fn input_ndarray(array: ArrayD<f32>, batch_axis: Option<usize>) -> Tensor {
+        tensor_funcs::input_ndarray(array, batch_axis)
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[inline]
+    fn input_ndarray_on<D: Device>(
+        array: ArrayD<f32>,
+        batch_axis: Option<usize>,
+        dev: Option<&mut D>,
+    ) -> Tensor {
+        tensor_funcs::input_ndarray_on(array, batch_axis, dev)
+    }
+
     #[inline]
     fn copy<T: AsRef<Tensor>>(x: T) -> Tensor {
         tensor_funcs::copy(x)
@@ -1310,6 +1481,11 @@ impl Functions<Tensor> for FuncImpls<Tensor> {
         tensor_funcs::pick(x, ids, dim)
     }
 
+    #[inline]
+    fn pick_named<T: AsRef<Tensor>>(x: T, ids: &[u32], name: &str) -> Tensor {
+        tensor_funcs::pick_named(x, ids, name)
+    }
+
     #[inline]
     fn slice<T: AsRef<Tensor>>(x: T, dim: u32, lower: u32, upper: u32) -> Tensor {
         tensor_funcs::slice(x, dim, lower, upper)
@@ -1320,11 +1496,21 @@ impl Functions<Tensor> for FuncImpls<Tensor> {
         tensor_funcs::split(x, dim, n)
     }
 
+    #[inline]
+    fn split_named<T: AsRef<Tensor>>(x: T, name: &str, n: u32) -> Vec<Tensor> {
+        tensor_funcs::split_named(x, name, n)
+    }
+
     #[inline]
     fn concat<TS: AsRef<[T]>, T: AsRef<Tensor>>(xs: TS, dim: u32) -> Tensor {
         tensor_funcs::concat(xs, dim)
     }
 
+    #[inline]
+    fn concat_named<TS: AsRef<[T]>, T: AsRef<Tensor>>(xs: TS, name: &str) -> Tensor {
+        tensor_funcs::concat_named(xs, name)
+    }
+
     #[inline]
     fn reshape<T: AsRef<Tensor>, S: Into<Shape>>(x: T, new_shape: S) -> Tensor {
         tensor_funcs::reshape(x, new_shape)
@@ -1435,6 +1621,11 @@ impl Functions<Tensor> for FuncImpls<Tensor> {
         tensor_funcs::sum(x, dim)
     }
 
+    #[inline]
+    fn sum_named<T: AsRef<Tensor>>(x: T, name: &str) -> Tensor {
+        tensor_funcs::sum_named(x, name)
+    }
+
     #[inline]
     fn sum_vars<TS: AsRef<[T]>, T: AsRef<Tensor>>(xs: TS) -> Tensor {
         tensor_funcs::sum_tensors(xs)
@@ -1,4 +1,83 @@
+use shape::DimNames;
+use Shape;
+
 mod generics;
 pub mod node_funcs;
 pub mod tensor_funcs;
 pub use self::generics::exports::*;
+
+/// Returns the dimension names of the result of an elementwise operation, which are the names
+/// of the dimensions in either operand.
+///
+/// Panics with the shapes given by `shapes` if the operands give different names to the same
+/// dimension, or the same name to different dimensions, e.g. when one operand is transposed.
+pub(crate) fn elementwise_dim_names<F: FnOnce() -> (Shape, Shape)>(
+    a: DimNames,
+    b: DimNames,
+    shapes: F,
+) -> DimNames {
+    let conflicts = a.iter().zip(&b).any(|pair| match pair {
+        (&Some(x), &Some(y)) => x != y,
+        (&None, &Some(y)) => a.contains(&Some(y)),
+        (&Some(x), &None) => b.contains(&Some(x)),
+        (&None, &None) => false,
+    });
+    if conflicts {
+        let (a, b) = shapes();
+        panic!(
+            "dimension names of operands do not match: {:?} and {:?}",
+            a, b
+        );
+    }
+    let mut names = a;
+    for (name, &other) in names.iter_mut().zip(&b) {
+        *name = name.or(other);
+    }
+    names
+}
+
+/// Returns the dimension of `shape` which has the specified name.
+///
+/// Panics if there is no such dimension.
+pub(crate) fn named_dim(shape: &Shape, name: &str) -> u32 {
+    shape
+        .dim_by_name(name)
+        .unwrap_or_else(|| panic!("no dimension named `{}` in {:?}", name, shape))
+}
+
+/// Concatenates samples into a minibatched shape and a flat list of values.
+///
+/// Panics if `shape` already has a minibatch, `samples` is empty, or the length of any sample
+/// differs from `shape.volume()`.
+pub(crate) fn concat_samples<S: AsRef<[f32]>>(samples: &[S], shape: Shape) -> (Shape, Vec<f32>) {
+    assert!(!samples.is_empty(), "samples must not be empty");
+    assert!(
+        !shape.has_batch(),
+        "shape of each sample must not have a minibatch: {}",
+        shape
+    );
+    let volume = shape.volume() as usize;
+    let mut values = Vec::with_capacity(volume * samples.len());
+    for (i, sample) in samples.iter().enumerate() {
+        let sample = sample.as_ref();
+        assert!(
+            sample.len() == volume,
+            "sample {} has {} values, but the volume of {} is {}",
+            i,
+            sample.len(),
+            shape,
+            volume
+        );
+        values.extend_from_slice(sample);
+    }
+    (shape.resize_batch(samples.len() as u32), values)
+}
+
+/// Splits a flat list of values into samples of `shape`.
+pub(crate) fn split_samples(shape: &Shape, values: Vec<f32>) -> Vec<Vec<f32>> {
+    let volume = shape.volume() as usize;
+    values
+        .chunks(volume)
+        .map(|sample| sample.to_vec())
+        .collect()
+}
@@ -1,4 +1,8 @@
 use devices::AnyDevice;
+#[cfg(feature = "ndarray")]
+use ndarray::ArrayD;
+#[cfg(feature = "ndarray")]
+use ndarray_impl;
 use primitiv_sys as _primitiv;
 use std::ops;
 use std::ptr;
@@ -23,6 +27,14 @@ macro_rules! node_func_body {
     }
 }
 
+macro_rules! named_node_func_body {
+    ($names:expr, $api_fn:ident, $($arg:expr),*) => {{
+        let mut y = node_func_body!($api_fn, $($arg),*);
+        y.set_dim_names($names);
+        y
+    }}
+}
+
 macro_rules! impl_node_unary_func {
     ($name:ident, $api_fn:ident) => {
         pub fn $name<N: AsRef<Node>>(x: N) -> Node {
@@ -31,6 +43,15 @@ macro_rules! impl_node_unary_func {
     };
 }
 
+macro_rules! impl_node_elementwise_func {
+    ($name:ident, $api_fn:ident) => {
+        pub fn $name<N: AsRef<Node>>(x: N) -> Node {
+            let x = x.as_ref();
+            named_node_func_body!(x.dim_names(), $api_fn, x.as_ptr())
+        }
+    };
+}
+
 macro_rules! impl_node_binary_func {
     (
         $name:ident,
@@ -41,15 +62,21 @@ macro_rules! impl_node_binary_func {
         $api_fn_cx:ident
     ) => {
         pub fn $name<N1: AsRef<Node>, N2: AsRef<Node>>(a: N1, b: N2) -> Node {
-            node_func_body!($api_fn, a.as_ref().as_ptr(), b.as_ref().as_ptr())
+            let (a, b) = (a.as_ref(), b.as_ref());
+            let names = super::elementwise_dim_names(a.dim_names(), b.dim_names(), || {
+                (a.shape(), b.shape())
+            });
+            named_node_func_body!(names, $api_fn, a.as_ptr(), b.as_ptr())
         }
 
         pub fn $name_xc<N: AsRef<Node>>(x: N, k: f32) -> Node {
-            node_func_body!($api_fn_xc, x.as_ref().as_ptr(), k)
+            let x = x.as_ref();
+            named_node_func_body!(x.dim_names(), $api_fn_xc, x.as_ptr(), k)
         }
 
         pub fn $name_cx<N: AsRef<Node>>(k: f32, x: N) -> Node {
-            node_func_body!($api_fn_cx, k, x.as_ref().as_ptr())
+            let x = x.as_ref();
+            named_node_func_body!(x.dim_names(), $api_fn_cx, k, x.as_ptr())
         }
     };
 }
@@ -60,7 +87,7 @@ macro_rules! impl_node_unary_op {
             type Output = Node;
 
             fn $op_fn(self) -> Node {
-                node_func_body!($api_fn, self.as_ptr())
+                named_node_func_body!(self.dim_names(), $api_fn, self.as_ptr())
             }
         }
     };
@@ -72,7 +99,7 @@ macro_rules! impl_node_binary_with_constant_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: $scalar) -> Node {
-                node_func_body!($api_fn_xc, self.as_ptr(), rhs as f32)
+                named_node_func_body!(self.dim_names(), $api_fn_xc, self.as_ptr(), rhs as f32)
             }
         }
 
@@ -80,7 +107,7 @@ macro_rules! impl_node_binary_with_constant_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: $scalar) -> Node {
-                node_func_body!($api_fn_xc, self.as_ptr(), rhs as f32)
+                named_node_func_body!(self.dim_names(), $api_fn_xc, self.as_ptr(), rhs as f32)
             }
         }
 
@@ -88,7 +115,7 @@ macro_rules! impl_node_binary_with_constant_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: Node) -> Node {
-                node_func_body!($api_fn_cx, self as f32, rhs.as_ptr())
+                named_node_func_body!(rhs.dim_names(), $api_fn_cx, self as f32, rhs.as_ptr())
             }
         }
 
@@ -96,7 +123,7 @@ macro_rules! impl_node_binary_with_constant_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: &'a Node) -> Node {
-                node_func_body!($api_fn_cx, self as f32, rhs.as_ptr())
+                named_node_func_body!(rhs.dim_names(), $api_fn_cx, self as f32, rhs.as_ptr())
             }
         }
     };
@@ -119,7 +146,10 @@ macro_rules! impl_node_binary_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: Node) -> Node {
-                node_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_node_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
 
@@ -127,7 +157,10 @@ macro_rules! impl_node_binary_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: Node) -> Node {
-                node_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_node_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
 
@@ -135,7 +168,10 @@ macro_rules! impl_node_binary_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: &'a Node) -> Node {
-                node_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_node_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
 
@@ -143,14 +179,17 @@ macro_rules! impl_node_binary_op {
             type Output = Node;
 
             fn $op_fn(self, rhs: &'a Node) -> Node {
-                node_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_node_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
     };
 }
 
-impl_node_unary_func!(positive, primitivApplyNodePositive);
-impl_node_unary_func!(negative, primitivApplyNodeNegative);
+impl_node_elementwise_func!(positive, primitivApplyNodePositive);
+impl_node_elementwise_func!(negative, primitivApplyNodeNegative);
 impl_node_unary_op!(Neg, neg, primitivApplyNodeNegative);
 impl_node_binary_func!(
     add,
@@ -222,7 +261,8 @@ impl_node_binary_func!(
 );
 
 pub fn pown<N: AsRef<Node>>(x: N, k: i32) -> Node {
-    node_func_body!(primitivApplyNodePowN, x.as_ref().as_ptr(), k)
+    let x = x.as_ref();
+    named_node_func_body!(x.dim_names(), primitivApplyNodePowN, x.as_ptr(), k)
 }
 
 pub fn input<S: Into<Shape>>(shape: S, data: &[f32]) -> Node {
@@ -241,7 +281,7 @@ pub fn input_into<S: Into<Shape>, D: Device>(
 ) -> Node {
     node_func_body!(
         primitivApplyNodeInput,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         data.as_ptr(),
         data.len(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
@@ -249,6 +289,53 @@ pub fn input_into<S: Into<Shape>, D: Device>(
     )
 }
 
+pub fn input_batch<TS: AsRef<[f32]>, S: Into<Shape>>(samples: &[TS], shape: S) -> Node {
+    input_batch_into::<TS, S, AnyDevice>(samples, shape, None, None)
+}
+
+pub fn input_batch_on<TS: AsRef<[f32]>, S: Into<Shape>, D: Device>(
+    samples: &[TS],
+    shape: S,
+    dev: Option<&mut D>,
+) -> Node {
+    input_batch_into::<TS, S, D>(samples, shape, dev, None)
+}
+
+pub fn input_batch_into<TS: AsRef<[f32]>, S: Into<Shape>, D: Device>(
+    samples: &[TS],
+    shape: S,
+    dev: Option<&mut D>,
+    g: Option<&mut Graph>,
+) -> Node {
+    let (shape, values) = super::concat_samples(samples, shape.into());
+    input_into(shape, &values, dev, g)
+}
+
+#[cfg(feature = "ndarray")]
+pub fn input_ndarray(array: ArrayD<f32>, batch_axis: Option<usize>) -> Node {
+    input_ndarray_into::<AnyDevice>(array, batch_axis, None, None)
+}
+
+#[cfg(feature = "ndarray")]
+pub fn input_ndarray_on<D: Device>(
+    array: ArrayD<f32>,
+    batch_axis: Option<usize>,
+    dev: Option<&mut D>,
+) -> Node {
+    input_ndarray_into::<D>(array, batch_axis, dev, None)
+}
+
+#[cfg(feature = "ndarray")]
+pub fn input_ndarray_into<D: Device>(
+    array: ArrayD<f32>,
+    batch_axis: Option<usize>,
+    dev: Option<&mut D>,
+    g: Option<&mut Graph>,
+) -> Node {
+    let (shape, values) = ndarray_impl::to_shape_and_values(array, batch_axis);
+    input_into(shape, &values, dev, g)
+}
+
 pub fn parameter(param: &mut Parameter) -> Node {
     parameter_into(param, None)
 }
@@ -266,9 +353,11 @@ pub fn copy<N: AsRef<Node>>(x: N) -> Node {
 }
 
 pub fn copy_on<N: AsRef<Node>, D: Device>(x: N, dev: Option<&mut D>) -> Node {
-    node_func_body!(
+    let x = x.as_ref();
+    named_node_func_body!(
+        x.dim_names(),
         primitivApplyNodeCopy,
-        x.as_ref().as_ptr(),
+        x.as_ptr(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
 }
@@ -283,6 +372,14 @@ pub fn pick<N: AsRef<Node>>(x: N, ids: &[u32], dim: u32) -> Node {
     )
 }
 
+pub fn pick_named<N: AsRef<Node>>(x: N, ids: &[u32], name: &str) -> Node {
+    let x = x.as_ref();
+    let shape = x.shape();
+    let mut y = pick(x, ids, super::named_dim(&shape, name));
+    y.set_dim_names(shape.dim_names());
+    y
+}
+
 pub fn slice<N: AsRef<Node>>(x: N, dim: u32, lower: u32, upper: u32) -> Node {
     node_func_body!(
         primitivApplyNodeSlice,
@@ -309,6 +406,16 @@ pub fn split<N: AsRef<Node>>(x: N, dim: u32, n: u32) -> Vec<Node> {
     }
 }
 
+pub fn split_named<N: AsRef<Node>>(x: N, name: &str, n: u32) -> Vec<Node> {
+    let x = x.as_ref();
+    let shape = x.shape();
+    let mut ys = split(x, super::named_dim(&shape, name), n);
+    for y in &mut ys {
+        y.set_dim_names(shape.dim_names());
+    }
+    ys
+}
+
 pub fn concat<NS: AsRef<[N]>, N: AsRef<Node>>(xs: NS, dim: u32) -> Node {
     let x_ptrs = xs
         .as_ref()
@@ -318,11 +425,23 @@ pub fn concat<NS: AsRef<[N]>, N: AsRef<Node>>(xs: NS, dim: u32) -> Node {
     node_func_body!(primitivApplyNodeConcat, x_ptrs.as_ptr(), x_ptrs.len(), dim)
 }
 
+pub fn concat_named<NS: AsRef<[N]>, N: AsRef<Node>>(xs: NS, name: &str) -> Node {
+    let shape = xs
+        .as_ref()
+        .first()
+        .expect("no node to concatenate")
+        .as_ref()
+        .shape();
+    let mut y = concat(xs, super::named_dim(&shape, name));
+    y.set_dim_names(shape.dim_names());
+    y
+}
+
 pub fn reshape<N: AsRef<Node>, S: Into<Shape>>(x: N, new_shape: S) -> Node {
     node_func_body!(
         primitivApplyNodeReshape,
         x.as_ref().as_ptr(),
-        new_shape.into().as_ptr()
+        new_shape.into().to_raw().as_ptr()
     )
 }
 
@@ -337,28 +456,30 @@ pub fn matmul<N1: AsRef<Node>, N2: AsRef<Node>>(a: N1, b: N2) -> Node {
     )
 }
 
-impl_node_unary_func!(abs, primitivApplyNodeAbs);
-impl_node_unary_func!(sqrt, primitivApplyNodeSqrt);
-impl_node_unary_func!(exp, primitivApplyNodeExp);
-impl_node_unary_func!(log, primitivApplyNodeLog);
-impl_node_unary_func!(tanh, primitivApplyNodeTanh);
-impl_node_unary_func!(sigmoid, primitivApplyNodeSigmoid);
-impl_node_unary_func!(softplus, primitivApplyNodeSoftplus);
-impl_node_unary_func!(sin, primitivApplyNodeSin);
-impl_node_unary_func!(cos, primitivApplyNodeCos);
-impl_node_unary_func!(tan, primitivApplyNodeTan);
-impl_node_unary_func!(relu, primitivApplyNodeRelu);
-impl_node_unary_func!(lrelu, primitivApplyNodeLrelu);
+impl_node_elementwise_func!(abs, primitivApplyNodeAbs);
+impl_node_elementwise_func!(sqrt, primitivApplyNodeSqrt);
+impl_node_elementwise_func!(exp, primitivApplyNodeExp);
+impl_node_elementwise_func!(log, primitivApplyNodeLog);
+impl_node_elementwise_func!(tanh, primitivApplyNodeTanh);
+impl_node_elementwise_func!(sigmoid, primitivApplyNodeSigmoid);
+impl_node_elementwise_func!(softplus, primitivApplyNodeSoftplus);
+impl_node_elementwise_func!(sin, primitivApplyNodeSin);
+impl_node_elementwise_func!(cos, primitivApplyNodeCos);
+impl_node_elementwise_func!(tan, primitivApplyNodeTan);
+impl_node_elementwise_func!(relu, primitivApplyNodeRelu);
+impl_node_elementwise_func!(lrelu, primitivApplyNodeLrelu);
 
 pub fn prelu<N: AsRef<Node>>(x: N, a: f32) -> Node {
-    node_func_body!(primitivApplyNodePrelu, x.as_ref().as_ptr(), a)
+    let x = x.as_ref();
+    named_node_func_body!(x.dim_names(), primitivApplyNodePrelu, x.as_ptr(), a)
 }
 
 pub fn elu<N: AsRef<Node>>(x: N, a: f32) -> Node {
-    node_func_body!(primitivApplyNodeElu, x.as_ref().as_ptr(), a)
+    let x = x.as_ref();
+    named_node_func_body!(x.dim_names(), primitivApplyNodeElu, x.as_ptr(), a)
 }
 
-impl_node_unary_func!(selu, primitivApplyNodeSelu);
+impl_node_elementwise_func!(selu, primitivApplyNodeSelu);
 
 pub fn max<N: AsRef<Node>>(x: N, dim: u32) -> Node {
     node_func_body!(primitivApplyNodeMax, x.as_ref().as_ptr(), dim)
@@ -372,6 +493,14 @@ pub fn sum<N: AsRef<Node>>(x: N, dim: u32) -> Node {
     node_func_body!(primitivApplyNodeSum, x.as_ref().as_ptr(), dim)
 }
 
+pub fn sum_named<N: AsRef<Node>>(x: N, name: &str) -> Node {
+    let x = x.as_ref();
+    let shape = x.shape();
+    let mut y = sum(x, super::named_dim(&shape, name));
+    y.set_dim_names(shape.dim_names());
+    y
+}
+
 pub fn sum_nodes<NS: AsRef<[N]>, N: AsRef<Node>>(xs: NS) -> Node {
     let x_ptrs = xs
         .as_ref()
@@ -429,7 +558,7 @@ pub fn softmax_cross_entropy_with_ids<N: AsRef<Node>>(x: N, ids: &[u32], dim: u3
     )
 }
 
-impl_node_unary_func!(stop_gradient, primitivApplyNodeStopGradient);
+impl_node_elementwise_func!(stop_gradient, primitivApplyNodeStopGradient);
 
 pub fn conv2d<N1: AsRef<Node>, N2: AsRef<Node>>(
     x: N1,
@@ -491,7 +620,7 @@ pub fn constant_into<S: Into<Shape>, D: Device>(
 ) -> Node {
     node_func_body!(
         primitivApplyNodeConstant,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         k,
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
         g.map(|_g| _g.as_mut_ptr()).unwrap_or(ptr::null_mut())
@@ -530,7 +659,7 @@ pub fn zeros_into<S: Into<Shape>, D: Device>(
 ) -> Node {
     node_func_body!(
         primitivApplyNodeZeros,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
         g.map(|_g| _g.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
@@ -551,7 +680,7 @@ pub fn ones_into<S: Into<Shape>, D: Device>(
 ) -> Node {
     node_func_body!(
         primitivApplyNodeOnes,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
         g.map(|_g| _g.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
@@ -593,7 +722,7 @@ pub mod random {
     ) -> Node {
         node_func_body!(
             primitivApplyNodeRandomBernoulli,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             p,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
             g.map(|_g| _g.as_mut_ptr()).unwrap_or(ptr::null_mut())
@@ -622,7 +751,7 @@ pub mod random {
     ) -> Node {
         node_func_body!(
             primitivApplyNodeRandomUniform,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             lower,
             upper,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
@@ -652,7 +781,7 @@ pub mod random {
     ) -> Node {
         node_func_body!(
             primitivApplyNodeRandomNormal,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             mean,
             sd,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
@@ -682,7 +811,7 @@ pub mod random {
     ) -> Node {
         node_func_body!(
             primitivApplyNodeRandomLogNormal,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             mean,
             sd,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
@@ -712,7 +841,7 @@ pub mod random {
     ) -> Node {
         node_func_body!(
             primitivApplyNodeRandomNormal,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             mu,
             beta,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
@@ -1,4 +1,8 @@
 use devices::AnyDevice;
+#[cfg(feature = "ndarray")]
+use ndarray::ArrayD;
+#[cfg(feature = "ndarray")]
+use ndarray_impl;
 use primitiv_sys as _primitiv;
 use std::ops;
 use std::ptr;
@@ -22,6 +26,14 @@ macro_rules! tensor_func_body {
     }
 }
 
+macro_rules! named_tensor_func_body {
+    ($names:expr, $api_fn:ident, $($arg:expr),*) => {{
+        let mut y = tensor_func_body!($api_fn, $($arg),*);
+        y.set_dim_names($names);
+        y
+    }}
+}
+
 macro_rules! impl_tensor_unary_func {
     ($name:ident, $api_fn:ident) => {
         pub fn $name<T: AsRef<Tensor>>(x: T) -> Tensor {
@@ -30,6 +42,15 @@ macro_rules! impl_tensor_unary_func {
     };
 }
 
+macro_rules! impl_tensor_elementwise_func {
+    ($name:ident, $api_fn:ident) => {
+        pub fn $name<T: AsRef<Tensor>>(x: T) -> Tensor {
+            let x = x.as_ref();
+            named_tensor_func_body!(x.dim_names(), $api_fn, x.as_ptr())
+        }
+    };
+}
+
 macro_rules! impl_tensor_binary_func {
     (
         $name:ident,
@@ -40,15 +61,21 @@ macro_rules! impl_tensor_binary_func {
         $api_fn_cx:ident
     ) => {
         pub fn $name<T1: AsRef<Tensor>, T2: AsRef<Tensor>>(a: T1, b: T2) -> Tensor {
-            tensor_func_body!($api_fn, a.as_ref().as_ptr(), b.as_ref().as_ptr())
+            let (a, b) = (a.as_ref(), b.as_ref());
+            let names = super::elementwise_dim_names(a.dim_names(), b.dim_names(), || {
+                (a.shape(), b.shape())
+            });
+            named_tensor_func_body!(names, $api_fn, a.as_ptr(), b.as_ptr())
         }
 
         pub fn $name_xc<T: AsRef<Tensor>>(x: T, k: f32) -> Tensor {
-            tensor_func_body!($api_fn_xc, x.as_ref().as_ptr(), k)
+            let x = x.as_ref();
+            named_tensor_func_body!(x.dim_names(), $api_fn_xc, x.as_ptr(), k)
         }
 
         pub fn $name_cx<T: AsRef<Tensor>>(k: f32, x: T) -> Tensor {
-            tensor_func_body!($api_fn_cx, k, x.as_ref().as_ptr())
+            let x = x.as_ref();
+            named_tensor_func_body!(x.dim_names(), $api_fn_cx, k, x.as_ptr())
         }
     };
 }
@@ -59,7 +86,7 @@ macro_rules! impl_tensor_unary_op {
             type Output = Tensor;
 
             fn $op_fn(self) -> Tensor {
-                tensor_func_body!($api_fn, self.as_ptr())
+                named_tensor_func_body!(self.dim_names(), $api_fn, self.as_ptr())
             }
         }
     };
@@ -71,7 +98,7 @@ macro_rules! impl_tensor_binary_with_constant_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: $scalar) -> Tensor {
-                tensor_func_body!($api_fn_xc, self.as_ptr(), rhs as f32)
+                named_tensor_func_body!(self.dim_names(), $api_fn_xc, self.as_ptr(), rhs as f32)
             }
         }
 
@@ -79,7 +106,7 @@ macro_rules! impl_tensor_binary_with_constant_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: $scalar) -> Tensor {
-                tensor_func_body!($api_fn_xc, self.as_ptr(), rhs as f32)
+                named_tensor_func_body!(self.dim_names(), $api_fn_xc, self.as_ptr(), rhs as f32)
             }
         }
 
@@ -87,7 +114,7 @@ macro_rules! impl_tensor_binary_with_constant_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: Tensor) -> Tensor {
-                tensor_func_body!($api_fn_cx, self as f32, rhs.as_ptr())
+                named_tensor_func_body!(rhs.dim_names(), $api_fn_cx, self as f32, rhs.as_ptr())
             }
         }
 
@@ -95,7 +122,7 @@ macro_rules! impl_tensor_binary_with_constant_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: &'a Tensor) -> Tensor {
-                tensor_func_body!($api_fn_cx, self as f32, rhs.as_ptr())
+                named_tensor_func_body!(rhs.dim_names(), $api_fn_cx, self as f32, rhs.as_ptr())
             }
         }
     };
@@ -118,7 +145,10 @@ macro_rules! impl_tensor_binary_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: Tensor) -> Tensor {
-                tensor_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_tensor_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
 
@@ -126,7 +156,10 @@ macro_rules! impl_tensor_binary_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: Tensor) -> Tensor {
-                tensor_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_tensor_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
 
@@ -134,7 +167,10 @@ macro_rules! impl_tensor_binary_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: &'a Tensor) -> Tensor {
-                tensor_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_tensor_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
 
@@ -142,14 +178,17 @@ macro_rules! impl_tensor_binary_op {
             type Output = Tensor;
 
             fn $op_fn(self, rhs: &'a Tensor) -> Tensor {
-                tensor_func_body!($api_fn, self.as_ptr(), rhs.as_ptr())
+                let names = super::elementwise_dim_names(self.dim_names(), rhs.dim_names(), || {
+                    (self.shape(), rhs.shape())
+                });
+                named_tensor_func_body!(names, $api_fn, self.as_ptr(), rhs.as_ptr())
             }
         }
     };
 }
 
-impl_tensor_unary_func!(positive, primitivApplyTensorPositive);
-impl_tensor_unary_func!(negative, primitivApplyTensorNegative);
+impl_tensor_elementwise_func!(positive, primitivApplyTensorPositive);
+impl_tensor_elementwise_func!(negative, primitivApplyTensorNegative);
 impl_tensor_unary_op!(Neg, neg, primitivApplyTensorNegative);
 impl_tensor_binary_func!(
     add,
@@ -221,7 +260,8 @@ impl_tensor_binary_func!(
 );
 
 pub fn pown<T: AsRef<Tensor>>(x: T, k: i32) -> Tensor {
-    tensor_func_body!(primitivApplyTensorPowN, x.as_ref().as_ptr(), k)
+    let x = x.as_ref();
+    named_tensor_func_body!(x.dim_names(), primitivApplyTensorPowN, x.as_ptr(), k)
 }
 
 pub fn input<S: Into<Shape>>(shape: S, data: &[f32]) -> Tensor {
@@ -231,13 +271,41 @@ pub fn input<S: Into<Shape>>(shape: S, data: &[f32]) -> Tensor {
 pub fn input_on<S: Into<Shape>, D: Device>(shape: S, data: &[f32], dev: Option<&mut D>) -> Tensor {
     tensor_func_body!(
         primitivApplyTensorInput,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         data.as_ptr(),
         data.len(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
 }
 
+pub fn input_batch<TS: AsRef<[f32]>, S: Into<Shape>>(samples: &[TS], shape: S) -> Tensor {
+    input_batch_on::<TS, S, AnyDevice>(samples, shape, None)
+}
+
+pub fn input_batch_on<TS: AsRef<[f32]>, S: Into<Shape>, D: Device>(
+    samples: &[TS],
+    shape: S,
+    dev: Option<&mut D>,
+) -> Tensor {
+    let (shape, values) = super::concat_samples(samples, shape.into());
+    input_on(shape, &values, dev)
+}
+
+#[cfg(feature = "ndarray")]
+pub fn input_ndarray(array: ArrayD<f32>, batch_axis: Option<usize>) -> Tensor {
+    input_ndarray_on::<AnyDevice>(array, batch_axis, None)
+}
+
+#[cfg(feature = "ndarray")]
+pub fn input_ndarray_on<D: Device>(
+    array: ArrayD<f32>,
+    batch_axis: Option<usize>,
+    dev: Option<&mut D>,
+) -> Tensor {
+    let (shape, values) = ndarray_impl::to_shape_and_values(array, batch_axis);
+    input_on(shape, &values, dev)
+}
+
 pub fn parameter(param: &mut Parameter) -> Tensor {
     tensor_func_body!(primitivApplyTensorParameter, param.as_mut_ptr())
 }
@@ -247,9 +315,11 @@ pub fn copy<T: AsRef<Tensor>>(x: T) -> Tensor {
 }
 
 pub fn copy_on<T: AsRef<Tensor>, D: Device>(x: T, dev: Option<&mut D>) -> Tensor {
-    tensor_func_body!(
+    let x = x.as_ref();
+    named_tensor_func_body!(
+        x.dim_names(),
         primitivApplyTensorCopy,
-        x.as_ref().as_ptr(),
+        x.as_ptr(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
 }
@@ -264,6 +334,14 @@ pub fn pick<T: AsRef<Tensor>>(x: T, ids: &[u32], dim: u32) -> Tensor {
     )
 }
 
+pub fn pick_named<T: AsRef<Tensor>>(x: T, ids: &[u32], name: &str) -> Tensor {
+    let x = x.as_ref();
+    let shape = x.shape();
+    let mut y = pick(x, ids, super::named_dim(&shape, name));
+    y.set_dim_names(shape.dim_names());
+    y
+}
+
 pub fn slice<T: AsRef<Tensor>>(x: T, dim: u32, lower: u32, upper: u32) -> Tensor {
     tensor_func_body!(
         primitivApplyTensorSlice,
@@ -290,6 +368,16 @@ pub fn split<T: AsRef<Tensor>>(x: T, dim: u32, n: u32) -> Vec<Tensor> {
     }
 }
 
+pub fn split_named<T: AsRef<Tensor>>(x: T, name: &str, n: u32) -> Vec<Tensor> {
+    let x = x.as_ref();
+    let shape = x.shape();
+    let mut ys = split(x, super::named_dim(&shape, name), n);
+    for y in &mut ys {
+        y.set_dim_names(shape.dim_names());
+    }
+    ys
+}
+
 pub fn concat<TS: AsRef<[T]>, T: AsRef<Tensor>>(xs: TS, dim: u32) -> Tensor {
     let x_ptrs = xs
         .as_ref()
@@ -304,11 +392,23 @@ pub fn concat<TS: AsRef<[T]>, T: AsRef<Tensor>>(xs: TS, dim: u32) -> Tensor {
     )
 }
 
+pub fn concat_named<TS: AsRef<[T]>, T: AsRef<Tensor>>(xs: TS, name: &str) -> Tensor {
+    let shape = xs
+        .as_ref()
+        .first()
+        .expect("no tensor to concatenate")
+        .as_ref()
+        .shape();
+    let mut y = concat(xs, super::named_dim(&shape, name));
+    y.set_dim_names(shape.dim_names());
+    y
+}
+
 pub fn reshape<T: AsRef<Tensor>, S: Into<Shape>>(x: T, new_shape: S) -> Tensor {
     tensor_func_body!(
         primitivApplyTensorReshape,
         x.as_ref().as_ptr(),
-        new_shape.into().as_ptr()
+        new_shape.into().to_raw().as_ptr()
     )
 }
 
@@ -323,28 +423,30 @@ pub fn matmul<T1: AsRef<Tensor>, T2: AsRef<Tensor>>(a: T1, b: T2) -> Tensor {
     )
 }
 
-impl_tensor_unary_func!(abs, primitivApplyTensorAbs);
-impl_tensor_unary_func!(sqrt, primitivApplyTensorSqrt);
-impl_tensor_unary_func!(exp, primitivApplyTensorExp);
-impl_tensor_unary_func!(log, primitivApplyTensorLog);
-impl_tensor_unary_func!(tanh, primitivApplyTensorTanh);
-impl_tensor_unary_func!(sigmoid, primitivApplyTensorSigmoid);
-impl_tensor_unary_func!(softplus, primitivApplyTensorSoftplus);
-impl_tensor_unary_func!(sin, primitivApplyTensorSin);
-impl_tensor_unary_func!(cos, primitivApplyTensorCos);
-impl_tensor_unary_func!(tan, primitivApplyTensorTan);
-impl_tensor_unary_func!(relu, primitivApplyTensorRelu);
-impl_tensor_unary_func!(lrelu, primitivApplyTensorLrelu);
+impl_tensor_elementwise_func!(abs, primitivApplyTensorAbs);
+impl_tensor_elementwise_func!(sqrt, primitivApplyTensorSqrt);
+impl_tensor_elementwise_func!(exp, primitivApplyTensorExp);
+impl_tensor_elementwise_func!(log, primitivApplyTensorLog);
+impl_tensor_elementwise_func!(tanh, primitivApplyTensorTanh);
+impl_tensor_elementwise_func!(sigmoid, primitivApplyTensorSigmoid);
+impl_tensor_elementwise_func!(softplus, primitivApplyTensorSoftplus);
+impl_tensor_elementwise_func!(sin, primitivApplyTensorSin);
+impl_tensor_elementwise_func!(cos, primitivApplyTensorCos);
+impl_tensor_elementwise_func!(tan, primitivApplyTensorTan);
+impl_tensor_elementwise_func!(relu, primitivApplyTensorRelu);
+impl_tensor_elementwise_func!(lrelu, primitivApplyTensorLrelu);
 
 pub fn prelu<T: AsRef<Tensor>>(x: T, a: f32) -> Tensor {
-    tensor_func_body!(primitivApplyTensorPrelu, x.as_ref().as_ptr(), a)
+    let x = x.as_ref();
+    named_tensor_func_body!(x.dim_names(), primitivApplyTensorPrelu, x.as_ptr(), a)
 }
 
 pub fn elu<T: AsRef<Tensor>>(x: T, a: f32) -> Tensor {
-    tensor_func_body!(primitivApplyTensorElu, x.as_ref().as_ptr(), a)
+    let x = x.as_ref();
+    named_tensor_func_body!(x.dim_names(), primitivApplyTensorElu, x.as_ptr(), a)
 }
 
-impl_tensor_unary_func!(selu, primitivApplyTensorSelu);
+impl_tensor_elementwise_func!(selu, primitivApplyTensorSelu);
 
 pub fn max<T: AsRef<Tensor>>(x: T, dim: u32) -> Tensor {
     tensor_func_body!(primitivApplyTensorMax, x.as_ref().as_ptr(), dim)
@@ -358,6 +460,14 @@ pub fn sum<T: AsRef<Tensor>>(x: T, dim: u32) -> Tensor {
     tensor_func_body!(primitivApplyTensorSum, x.as_ref().as_ptr(), dim)
 }
 
+pub fn sum_named<T: AsRef<Tensor>>(x: T, name: &str) -> Tensor {
+    let x = x.as_ref();
+    let shape = x.shape();
+    let mut y = sum(x, super::named_dim(&shape, name));
+    y.set_dim_names(shape.dim_names());
+    y
+}
+
 pub fn sum_tensors<TS: AsRef<[T]>, T: AsRef<Tensor>>(xs: TS) -> Tensor {
     let x_ptrs = xs
         .as_ref()
@@ -423,7 +533,7 @@ pub fn softmax_cross_entropy_with_ids<T: AsRef<Tensor>>(x: T, ids: &[u32], dim:
     )
 }
 
-impl_tensor_unary_func!(stop_gradient, primitivApplyTensorStopGradient);
+impl_tensor_elementwise_func!(stop_gradient, primitivApplyTensorStopGradient);
 
 pub fn conv2d<T1: AsRef<Tensor>, T2: AsRef<Tensor>>(
     x: T1,
@@ -476,7 +586,7 @@ pub fn constant<S: Into<Shape>>(shape: S, k: f32) -> Tensor {
 pub fn constant_on<S: Into<Shape>, D: Device>(shape: S, k: f32, dev: Option<&mut D>) -> Tensor {
     tensor_func_body!(
         primitivApplyTensorConstant,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         k,
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
@@ -501,7 +611,7 @@ pub fn zeros<S: Into<Shape>>(shape: S) -> Tensor {
 pub fn zeros_on<S: Into<Shape>, D: Device>(shape: S, dev: Option<&mut D>) -> Tensor {
     tensor_func_body!(
         primitivApplyTensorZeros,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
 }
@@ -513,7 +623,7 @@ pub fn ones<S: Into<Shape>>(shape: S) -> Tensor {
 pub fn ones_on<S: Into<Shape>, D: Device>(shape: S, dev: Option<&mut D>) -> Tensor {
     tensor_func_body!(
         primitivApplyTensorOnes,
-        shape.into().as_ptr(),
+        shape.into().to_raw().as_ptr(),
         dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
     )
 }
@@ -548,7 +658,7 @@ pub mod random {
     ) -> Tensor {
         tensor_func_body!(
             primitivApplyTensorRandomBernoulli,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             p,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
         )
@@ -566,7 +676,7 @@ pub mod random {
     ) -> Tensor {
         tensor_func_body!(
             primitivApplyTensorRandomUniform,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             lower,
             upper,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
@@ -585,7 +695,7 @@ pub mod random {
     ) -> Tensor {
         tensor_func_body!(
             primitivApplyTensorRandomNormal,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             mean,
             sd,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
@@ -604,7 +714,7 @@ pub mod random {
     ) -> Tensor {
         tensor_func_body!(
             primitivApplyTensorRandomLogNormal,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             mean,
             sd,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
@@ -623,7 +733,7 @@ pub mod random {
     ) -> Tensor {
         tensor_func_body!(
             primitivApplyTensorRandomNormal,
-            shape.into().as_ptr(),
+            shape.into().to_raw().as_ptr(),
             mu,
             beta,
             dev.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut())
@@ -1,22 +1,136 @@
 use devices::AnyDevice;
+use display;
+use functions;
 use primitiv_sys as _primitiv;
+use shape::{DimNames, RawShape, MAX_DEPTH};
+use std::collections::HashMap;
 use std::ffi::CString;
+use std::fmt;
 use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
 use ApiResult;
+use Device;
 use Shape;
 use Tensor;
+use Variable;
 use Wrap;
 
+static NEXT_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    /// Current generation of each graph created on the Rust side, keyed by its address.
+    ///
+    /// Nodes share the counter of their graph, so the table is only looked up when nodes are
+    /// created. A dropped graph sets its counter to 0, which is never used as a generation.
+    static ref GRAPH_GENERATIONS: RwLock<HashMap<usize, Arc<AtomicUsize>>> =
+        RwLock::new(HashMap::new());
+}
+
+fn new_generation() -> usize {
+    NEXT_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
 /// Pointer of a node in the computation graph.
-#[derive(Debug)]
+///
+/// A node remembers the generation of the graph it was created on. Once the graph is cleared or
+/// dropped, the node becomes stale: `valid()` returns false and, by design, any other use of the
+/// node, including passing it to functions, panics instead of touching freed memory in the core
+/// library. Check `valid()` beforehand to handle stale nodes without panicking.
 pub struct Node {
     inner: NonNull<_primitiv::primitivNode_t>,
+    // Current generation of the graph, which is `None` if the graph is not created by this crate.
+    graph_generation: Option<Arc<AtomicUsize>>,
+    generation: usize,
+    dim_names: DimNames,
 }
 
-impl_wrap_owned!(Node, primitivNode_t);
-impl_drop!(Node, primitivDeleteNode);
+impl Wrap<_primitiv::primitivNode_t> for Node {
+    #[inline(always)]
+    fn from_raw(ptr: *mut _primitiv::primitivNode_t, _owned: bool) -> Self {
+        let inner = NonNull::new(ptr).expect("pointer must not be null");
+        unsafe {
+            let mut retval: u32 = 0;
+            check_api_status!(_primitiv::primitivIsValidNode(
+                inner.as_ptr(),
+                &mut retval as *mut _,
+            ));
+            if retval == 1 {
+                let mut graph_ptr: *mut _primitiv::primitivGraph_t = ptr::null_mut();
+                check_api_status!(_primitiv::primitivGetGraphFromNode(
+                    inner.as_ptr(),
+                    &mut graph_ptr,
+                ));
+                let graph_generation = GRAPH_GENERATIONS
+                    .read()
+                    .unwrap()
+                    .get(&(graph_ptr as usize))
+                    .cloned();
+                let generation = graph_generation
+                    .as_ref()
+                    .map_or(0, |generation| generation.load(Ordering::SeqCst));
+                Node {
+                    inner,
+                    graph_generation,
+                    generation,
+                    dim_names: [None; MAX_DEPTH],
+                }
+            } else {
+                Node {
+                    inner,
+                    graph_generation: None,
+                    generation: 0,
+                    dim_names: [None; MAX_DEPTH],
+                }
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn as_ptr(&self) -> *const _primitiv::primitivNode_t {
+        self.assert_not_stale();
+        self.inner.as_ptr()
+    }
+
+    #[inline(always)]
+    fn as_mut_ptr(&mut self) -> *mut _primitiv::primitivNode_t {
+        self.assert_not_stale();
+        self.inner.as_ptr()
+    }
+
+    #[inline(always)]
+    fn is_owned(&self) -> bool {
+        true
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        unsafe {
+            check_api_status!(_primitiv::primitivDeleteNode(self.inner.as_ptr()));
+        }
+    }
+}
 
 impl Node {
+    /// Returns whether the graph of this node was cleared or dropped after the node was created.
+    fn is_stale(&self) -> bool {
+        // Nodes without a graph, or on a graph which is not managed by this crate, are never
+        // regarded as stale.
+        self.graph_generation.as_ref().map_or(false, |generation| {
+            generation.load(Ordering::SeqCst) != self.generation
+        })
+    }
+
+    #[inline]
+    fn assert_not_stale(&self) {
+        if self.is_stale() {
+            panic!(
+                "the node is stale: its graph was cleared or dropped after the node was created"
+            );
+        }
+    }
+
     /// Creates a new Node object.
     pub fn new() -> Self {
         unsafe {
@@ -39,11 +153,16 @@ impl Node {
     }
 
     /// Returns whether the node is valid or not.
+    ///
+    /// Returns false also when the graph of this node was cleared or dropped.
     pub fn valid(&self) -> bool {
+        if self.is_stale() {
+            return false;
+        }
         unsafe {
             let mut retval: u32 = 0;
             check_api_status!(_primitiv::primitivIsValidNode(
-                self.as_ptr(),
+                self.inner.as_ptr(),
                 &mut retval as *mut _,
             ));
             retval == 1
@@ -74,7 +193,7 @@ impl Node {
         }
     }
 
-    /// Returns shape of the node.
+    /// Returns shape of the node, including the names of the dimensions.
     pub fn shape(&self) -> Shape {
         unsafe {
             let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
@@ -82,10 +201,29 @@ impl Node {
                 self.as_ptr(),
                 &mut shape_ptr,
             ));
-            Shape::from_raw(shape_ptr, true)
+            let mut shape = RawShape::from_raw(shape_ptr, true).to_shape();
+            shape.set_dim_names(self.dim_names);
+            shape
         }
     }
 
+    /// Returns the node with names given to the dimensions from the first one.
+    ///
+    /// The names are used by the `*_named` functions. They are passed on to the results of those
+    /// functions and of elementwise functions and operators, which keep the dimensions.
+    pub fn with_dim_names(mut self, names: &[&str]) -> Self {
+        self.dim_names = Shape::new().with_names(names).dim_names();
+        self
+    }
+
+    pub(crate) fn dim_names(&self) -> DimNames {
+        self.dim_names
+    }
+
+    pub(crate) fn set_dim_names(&mut self, names: DimNames) {
+        self.dim_names = names;
+    }
+
     /// Returns device of the node.
     pub fn device(&self) -> AnyDevice {
         unsafe {
@@ -135,7 +273,55 @@ impl Node {
         }
     }
 
+    /// Calculates the value of this node and returns it as a list of samples in the minibatch.
+    pub fn to_batches(&self) -> Vec<Vec<f32>> {
+        functions::split_samples(&self.shape(), self.to_vector())
+    }
+
+    /// Returns whether two nodes have the same shape and element-wise close values.
+    ///
+    /// See `Variable::allclose()` for details.
+    pub fn allclose<T: AsRef<Self>>(&self, other: T, rtol: f32, atol: f32) -> bool {
+        Variable::allclose(self, other, rtol, atol)
+    }
+
+    /// Returns the maximum absolute difference between the values of two nodes.
+    ///
+    /// Panics if the shapes differ.
+    pub fn max_abs_diff<T: AsRef<Self>>(&self, other: T) -> f32 {
+        Variable::max_abs_diff(self, other)
+    }
+
+    /// Calculates the value of this node and writes it into the given buffer.
+    ///
+    /// Unlike `to_vector()`, this function does not allocate a new vector.
+    ///
+    /// Panics if the length of `buffer` differs from the number of values in this node.
+    pub fn read_into(&self, buffer: &mut [f32]) {
+        unsafe {
+            let mut size: usize = 0;
+            check_api_status!(_primitiv::primitivEvaluateNodeAsArray(
+                self.as_ptr(),
+                ptr::null_mut(),
+                &mut size as *mut _,
+            ));
+            assert!(
+                buffer.len() == size,
+                "buffer length mismatch: expected {}, got {}",
+                size,
+                buffer.len()
+            );
+            check_api_status!(_primitiv::primitivEvaluateNodeAsArray(
+                self.as_ptr(),
+                buffer.as_mut_ptr(),
+                &mut size as *mut _,
+            ));
+        }
+    }
+
     /// Returns argmax indices along an axis of this node.
+    ///
+    /// Remark: The indices are returned on the host, since the core library has no integer tensors.
     pub fn argmax(&self, dim: u32) -> Vec<u32> {
         unsafe {
             let mut size: usize = 0;
@@ -157,6 +343,8 @@ impl Node {
     }
 
     /// Returns argmin indices along an axis of this node.
+    ///
+    /// Remark: As with `argmax()`, the indices are returned on the host.
     pub fn argmin(&self, dim: u32) -> Vec<u32> {
         unsafe {
             let mut size: usize = 0;
@@ -190,19 +378,69 @@ impl Clone for Node {
     fn clone(&self) -> Self {
         unsafe {
             let mut node_ptr: *mut _primitiv::primitivNode_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivCloneNode(self.as_ptr(), &mut node_ptr));
-            Node::from_raw(node_ptr, true)
+            check_api_status!(_primitiv::primitivCloneNode(
+                self.inner.as_ptr(),
+                &mut node_ptr,
+            ));
+            Node {
+                inner: NonNull::new(node_ptr).expect("pointer must not be null"),
+                graph_generation: self.graph_generation.clone(),
+                generation: self.generation,
+                dim_names: self.dim_names,
+            }
         }
     }
 
     #[inline]
     fn clone_from(&mut self, source: &Self) {
         unsafe {
-            check_api_status!(_primitiv::primitivDeleteNode(self.as_mut_ptr()));
+            check_api_status!(_primitiv::primitivDeleteNode(self.inner.as_ptr()));
             let mut node_ptr: *mut _primitiv::primitivNode_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivCloneNode(source.as_ptr(), &mut node_ptr));
+            check_api_status!(_primitiv::primitivCloneNode(
+                source.inner.as_ptr(),
+                &mut node_ptr,
+            ));
             self.inner = NonNull::new(node_ptr).expect("pointer must not be null");
+            self.graph_generation = source.graph_generation.clone();
+            self.generation = source.generation;
+            self.dim_names = source.dim_names;
+        }
+    }
+}
+
+impl fmt::Debug for Node {
+    /// Calculates the value of the node and shows its shape and summary as `Tensor`.
+    ///
+    /// The core library does not tell whether the value is already calculated, so this always
+    /// runs the forward pass up to the node, which reuses the values calculated before.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.valid() {
+            return f.debug_struct("Node").field("valid", &false).finish();
+        }
+        let stats = self.graph().forward(self).stats();
+        f.debug_struct("Node")
+            .field("shape", &format_args!("{}", self.shape()))
+            .field("min", &stats.min)
+            .field("max", &stats.max)
+            .field("mean", &stats.mean)
+            .finish()
+    }
+}
+
+impl fmt::Display for Node {
+    /// Calculates the value of the node and shows it in the same format as `Tensor`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.valid() {
+            return f.write_str("Node(invalid)");
         }
+        let shape = self.shape();
+        writeln!(
+            f,
+            "Node(shape={}, device={:?})",
+            shape,
+            self.device().kind()
+        )?;
+        display::write_values(f, &shape, &self.to_vector())
     }
 }
 
@@ -227,7 +465,23 @@ pub struct Graph {
 }
 
 impl_wrap!(Graph, primitivGraph_t);
-impl_drop!(Graph, primitivDeleteGraph);
+
+impl Drop for Graph {
+    fn drop(&mut self) {
+        if self.is_owned() {
+            if let Some(generation) = GRAPH_GENERATIONS
+                .write()
+                .unwrap()
+                .remove(&(self.as_ptr() as usize))
+            {
+                generation.store(0, Ordering::SeqCst);
+            }
+            unsafe {
+                check_api_status!(_primitiv::primitivDeleteGraph(self.as_mut_ptr()));
+            }
+        }
+    }
+}
 
 impl Graph {
     /// Creates a new Graph object.
@@ -235,6 +489,10 @@ impl Graph {
         unsafe {
             let mut graph_ptr: *mut _primitiv::primitivGraph_t = ptr::null_mut();
             check_api_status!(_primitiv::primitivCreateGraph(&mut graph_ptr));
+            GRAPH_GENERATIONS.write().unwrap().insert(
+                graph_ptr as usize,
+                Arc::new(AtomicUsize::new(new_generation())),
+            );
             Graph::from_raw(graph_ptr, true)
         }
     }
@@ -249,11 +507,18 @@ impl Graph {
     /// Clear all operators in the graph.
     ///
     /// Remark: After calling this method, all Node objects supplied by the graph itself is
-    /// invalidated.
+    /// invalidated. Using such nodes afterwards panics.
     pub fn clear(&mut self) {
         unsafe {
             check_api_status!(_primitiv::primitivClearGraph(self.as_mut_ptr()));
         }
+        if let Some(generation) = GRAPH_GENERATIONS
+            .read()
+            .unwrap()
+            .get(&(self.as_ptr() as usize))
+        {
+            generation.store(new_generation(), Ordering::SeqCst);
+        }
     }
 
     /// Calculates the value of given node.
@@ -272,6 +537,14 @@ impl Graph {
         }
     }
 
+    /// Calculates the values of given nodes.
+    ///
+    /// Intermediate results are shared among the nodes, so each operator in the graph is
+    /// calculated at most once.
+    pub fn forward_all(&mut self, nodes: &[&Node]) -> Vec<Tensor> {
+        nodes.iter().map(|node| self.forward(node)).collect()
+    }
+
     /// Calculates the backpropagation.
     pub fn backward(&mut self, node: &Node) {
         unsafe {
@@ -291,7 +564,7 @@ impl Graph {
                 node.as_ptr(),
                 &mut shape_ptr,
             ));
-            Shape::from_raw(shape_ptr, true)
+            RawShape::from_raw(shape_ptr, true).to_shape()
         }
     }
 
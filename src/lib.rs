@@ -5,6 +5,8 @@ extern crate primitiv_derive;
 #[macro_use]
 extern crate lazy_static;
 extern crate libc;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
 #[cfg(feature = "serialize")]
 extern crate serde;
 
@@ -18,8 +20,17 @@ pub(crate) use status::*;
 #[macro_use]
 mod util;
 pub use util::*;
+mod compare;
+#[doc(hidden)]
+pub use compare::{
+    assert_close as __assert_close, assert_shape as __assert_shape, DEFAULT_ATOL as __DEFAULT_ATOL,
+    DEFAULT_RTOL as __DEFAULT_RTOL,
+};
+mod constraint;
+pub use constraint::Constraint;
 #[macro_use]
 mod device;
+mod display;
 pub use device::Device;
 mod graph;
 pub use graph::{Graph, Node};
@@ -28,12 +39,13 @@ mod initializer;
 pub use initializer::Initializer;
 #[macro_use]
 mod model;
-pub(crate) use model::internal as model_internal;
 pub use model::Model;
 mod parameter;
-pub use parameter::Parameter;
+pub use parameter::{Parameter, StatsIter};
 mod shape;
-pub use shape::Shape;
+pub use shape::{concat_shape, Shape};
+mod summary;
+pub use summary::{Histogram, TensorStats};
 mod tensor;
 pub use tensor::Tensor;
 #[macro_use]
@@ -43,8 +55,16 @@ pub mod functions;
 pub use functions::node_funcs as node_functions;
 pub use functions::tensor_funcs as tensor_functions;
 pub use functions::Variable;
+pub mod constraints;
 pub mod devices;
 pub mod initializers;
+mod msgpack;
+#[cfg(feature = "ndarray")]
+mod ndarray_impl;
+mod npy;
 pub mod optimizers;
+pub mod prune;
+pub mod quantize;
 #[cfg(feature = "serialize")]
 mod serialize;
+pub mod typed;
@@ -1,7 +1,13 @@
+use devices::AnyDevice;
+use msgpack;
+use npy;
+use parameter;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::hash::Hasher;
 use std::io;
 use std::path::Path;
+use Constraint;
 use Device;
 use Parameter;
 use Wrap;
@@ -10,9 +16,15 @@ pub trait Model: Sized {
     /// Loads all parameters from a file.
     fn load<P: AsRef<Path>>(&mut self, path: P, with_stats: bool) -> io::Result<()> {
         self.register_parameters();
-        let lock = internal::get_entity_mut(self);
-        let mut entity = lock.write().unwrap();
-        entity.load(path, with_stats)
+        {
+            let lock = internal::get_entity_mut(self);
+            let mut entity = lock.write().unwrap();
+            entity.load(path.as_ref(), with_stats)?;
+        }
+        if with_stats {
+            record_stats_names(self, path)?;
+        }
+        Ok(())
     }
 
     /// Loads all parameters from a file.
@@ -23,9 +35,15 @@ pub trait Model: Sized {
         device: Option<&mut D>,
     ) -> io::Result<()> {
         self.register_parameters();
-        let lock = internal::get_entity_mut(self);
-        let mut entity = lock.write().unwrap();
-        entity.load_on(path, with_stats, device)
+        {
+            let lock = internal::get_entity_mut(self);
+            let mut entity = lock.write().unwrap();
+            entity.load_on(path.as_ref(), with_stats, device)?;
+        }
+        if with_stats {
+            record_stats_names(self, path)?;
+        }
+        Ok(())
     }
 
     /// Saves all parameters to a file.
@@ -35,6 +53,77 @@ pub trait Model: Sized {
         entity.save(path, with_stats)
     }
 
+    /// Saves all parameters to a `.npz` file.
+    ///
+    /// Each entry is keyed by the name hierarchy of the parameter joined with `/`, which can be
+    /// passed to `find_parameter` after splitting.
+    fn save_npz<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let entries = self
+            .get_all_parameters()
+            .into_iter()
+            .map(|(names, param)| {
+                let value = param.value();
+                (
+                    names.join("/"),
+                    npy::encode_npy(&value.shape(), &value.to_vector()),
+                )
+            })
+            .collect::<Vec<_>>();
+        npy::write_file(path, &npy::encode_npz(&entries)?)
+    }
+
+    /// Loads all parameters from a `.npz` file.
+    fn load_npz<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.load_npz_on::<P, AnyDevice>(path, None)
+    }
+
+    /// Loads all parameters from a `.npz` file onto the specified device.
+    ///
+    /// The file must have exactly one entry for each parameter in the model, with the same shape
+    /// as the parameter if it is initialized.
+    fn load_npz_on<P: AsRef<Path>, D: Device>(
+        &mut self,
+        path: P,
+        device: Option<&mut D>,
+    ) -> io::Result<()> {
+        self.register_parameters();
+        let mut params = self.get_all_parameters();
+        let mut values = vec![];
+        for (name, data) in npy::decode_npz(&npy::read_file(path)?)? {
+            let names = name.split('/').map(|s| s.to_string()).collect::<Vec<_>>();
+            let param = params.remove(&names).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown parameter in npz: {}", name),
+                )
+            })?;
+            let (shape, value) = npy::decode_npy(&data, None)?;
+            if param.valid() && shape != param.shape() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "shape mismatch in npz: {} is given for {} of {}",
+                        shape,
+                        name,
+                        param.shape()
+                    ),
+                ));
+            }
+            values.push((param, (shape, value)));
+        }
+        if let Some(names) = params.keys().next() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("missing parameter in npz: {}", names.join("/")),
+            ));
+        }
+        let mut device = device;
+        for (mut param, (shape, value)) in values {
+            param.init_by_values_on(shape, &value, device.as_mut().map(|d| &mut **d));
+        }
+        Ok(())
+    }
+
     /// Registers a new parameter.
     fn add_parameter(&mut self, name: &str, param: &mut Parameter) {
         let lock = internal::get_entity_mut(self);
@@ -68,6 +157,53 @@ pub trait Model: Sized {
         entity.find_parameter(names)
     }
 
+    /// Retrieves all parameters in the model and its submodels.
+    ///
+    /// Each parameter is keyed by its name hierarchy, as used in `find_parameter`. Handles
+    /// created by `Parameter::share()` are not included, so tied parameters appear only once.
+    fn get_all_parameters(&self) -> BTreeMap<Vec<String>, Parameter> {
+        let lock = internal::get_entity(self);
+        let entity = lock.read().unwrap();
+        entity.get_all_parameters()
+    }
+
+    /// Computes the global L2 norm of the gradients of the trainable parameters in the model and
+    /// its submodels, which is the norm clipped by `Optimizer::clip_grad_norm()`.
+    fn grad_norm(&mut self) -> f32 {
+        self.register_parameters();
+        parameter::gradient_norm(
+            self.get_all_parameters()
+                .values()
+                .filter(|param| param.is_trainable()),
+        )
+    }
+
+    /// Stops updating the parameter or all parameters in the submodel specified by the name
+    /// hierarchy. An empty hierarchy specifies the whole model.
+    ///
+    /// Panics if no parameter is found.
+    fn freeze(&mut self, names: &[&str]) {
+        set_trainable(self, names, false)
+    }
+
+    /// Restarts updating the parameter or all parameters in the submodel specified by the name
+    /// hierarchy. An empty hierarchy specifies the whole model.
+    ///
+    /// Panics if no parameter is found.
+    fn unfreeze(&mut self, names: &[&str]) {
+        set_trainable(self, names, true)
+    }
+
+    /// Attaches a constraint to the parameter or all parameters in the submodel specified by
+    /// the name hierarchy. An empty hierarchy specifies the whole model.
+    ///
+    /// Panics if no parameter is found.
+    fn constrain<C: Constraint + Clone + 'static>(&mut self, names: &[&str], constraint: C) {
+        for mut param in find_parameters_at(self, names) {
+            param.add_constraint(constraint.clone());
+        }
+    }
+
     /// Retrieves a submodel with specified name.
     fn get_submodel(&self, name: &str) -> Option<AnyModel> {
         {
@@ -107,6 +243,41 @@ pub trait Model: Sized {
     }
 }
 
+fn find_parameters_at<M: Model>(model: &mut M, names: &[&str]) -> Vec<Parameter> {
+    model.register_parameters();
+    let params = model
+        .get_all_parameters()
+        .into_iter()
+        .filter(|&(ref key, _)| {
+            key.len() >= names.len() && key.iter().zip(names).all(|(k, n)| k == n)
+        })
+        .map(|(_, param)| param)
+        .collect::<Vec<_>>();
+    assert!(
+        !params.is_empty(),
+        "no parameter found at {}",
+        names.join("/")
+    );
+    params
+}
+
+/// Records the names of the statistics loaded from a file to the parameters in the model.
+fn record_stats_names<M: Model, P: AsRef<Path>>(model: &M, path: P) -> io::Result<()> {
+    for (names, stats_names) in msgpack::read_model_stats_names(path)? {
+        let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+        if let Some(param) = model.find_parameter(&names) {
+            param.record_stats_names(stats_names);
+        }
+    }
+    Ok(())
+}
+
+fn set_trainable<M: Model>(model: &mut M, names: &[&str], trainable: bool) {
+    for mut param in find_parameters_at(model, names) {
+        param.set_trainable(trainable);
+    }
+}
+
 #[derive(Debug)]
 pub struct AnyModel;
 
@@ -124,7 +295,7 @@ pub(crate) mod internal {
     use super::Model;
     use devices::AnyDevice;
     use primitiv_sys as _primitiv;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
     use std::ffi::CString;
     use std::io;
     use std::path::Path;
@@ -138,6 +309,16 @@ pub(crate) mod internal {
     lazy_static! {
         static ref MODEL_MAP: RwLock<HashMap<u64, Arc<RwLock<ModelEntity>>>> =
             RwLock::new(HashMap::new());
+        // Names registered to each model object, because the core API can not enumerate them.
+        static ref MODEL_TREE: RwLock<HashMap<usize, ModelTree>> = RwLock::new(HashMap::new());
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct ModelTree {
+        parameters: BTreeSet<String>,
+        submodels: BTreeSet<String>,
+        // Handles created by `Parameter::share()`, which are not registered to the core model.
+        shared: BTreeMap<String, usize>,
     }
 
     pub(crate) struct UnwritableLock<T>(Arc<RwLock<T>>);
@@ -218,7 +399,20 @@ pub(crate) mod internal {
     }
 
     impl_wrap!(ModelEntity, primitivModel_t);
-    impl_drop!(ModelEntity, primitivDeleteModel);
+
+    impl Drop for ModelEntity {
+        fn drop(&mut self) {
+            if self.is_owned() {
+                MODEL_TREE
+                    .write()
+                    .unwrap()
+                    .remove(&(self.as_ptr() as usize));
+                unsafe {
+                    check_api_status!(_primitiv::primitivDeleteModel(self.as_mut_ptr()));
+                }
+            }
+        }
+    }
 
     unsafe impl Send for ModelEntity {}
     unsafe impl Sync for ModelEntity {}
@@ -274,6 +468,16 @@ pub(crate) mod internal {
 
         /// Registers a new parameter.
         pub fn add_parameter(&mut self, name: &str, param: &mut Parameter) {
+            if param.is_shared() {
+                MODEL_TREE
+                    .write()
+                    .unwrap()
+                    .entry(self.as_ptr() as usize)
+                    .or_insert_with(ModelTree::default)
+                    .shared
+                    .insert(name.to_string(), param.as_ptr() as usize);
+                return;
+            }
             unsafe {
                 let name_c = CString::new(name).unwrap();
                 let name_ptr = name_c.as_ptr();
@@ -283,6 +487,13 @@ pub(crate) mod internal {
                     param.as_mut_ptr(),
                 ));
             }
+            MODEL_TREE
+                .write()
+                .unwrap()
+                .entry(self.as_ptr() as usize)
+                .or_insert_with(ModelTree::default)
+                .parameters
+                .insert(name.to_string());
         }
 
         /// Registers a new submodel.
@@ -296,6 +507,13 @@ pub(crate) mod internal {
                     model.as_mut_ptr(),
                 ));
             }
+            MODEL_TREE
+                .write()
+                .unwrap()
+                .entry(self.as_ptr() as usize)
+                .or_insert_with(ModelTree::default)
+                .submodels
+                .insert(name.to_string());
         }
 
         /// Retrieves a parameter with specified name.
@@ -324,10 +542,27 @@ pub(crate) mod internal {
                     ),
                     (),
                 ).map(|()| Parameter::from_raw(parameter_ptr as *mut _, false));
-                result.ok()
+                result.ok().or_else(|| self.find_shared_parameter(names))
             }
         }
 
+        /// Searches a handle created by `Parameter::share()` with specified name hierarchy.
+        fn find_shared_parameter(&self, names: &[&str]) -> Option<Parameter> {
+            let (name, parents) = names.split_last()?;
+            let parent = if parents.is_empty() {
+                None
+            } else {
+                Some(self.find_submodel(parents)?)
+            };
+            let parent_ptr = parent.as_ref().unwrap_or(self).as_ptr() as usize;
+            MODEL_TREE
+                .read()
+                .unwrap()
+                .get(&parent_ptr)
+                .and_then(|tree| tree.shared.get(*name))
+                .map(|&param| unsafe { Parameter::from_raw(param as *mut _, false).share() })
+        }
+
         /// Retrieves a submodel with specified name.
         pub fn get_submodel(&self, name: &str) -> Option<ModelEntity> {
             self.find_submodel(&[name; 1])
@@ -358,7 +593,33 @@ pub(crate) mod internal {
             }
         }
 
-        // TODO(chantera): Implement get_all_parameters().
+        /// Retrieves all parameters in the model and its submodels.
+        ///
+        /// Handles created by `Parameter::share()` are not included.
+        pub fn get_all_parameters(&self) -> BTreeMap<Vec<String>, Parameter> {
+            let tree = MODEL_TREE
+                .read()
+                .unwrap()
+                .get(&(self.as_ptr() as usize))
+                .cloned()
+                .unwrap_or_default();
+            let mut params = BTreeMap::new();
+            for name in tree.parameters {
+                if let Some(param) = self.get_parameter(&name) {
+                    params.insert(vec![name], param);
+                }
+            }
+            for name in tree.submodels {
+                if let Some(submodel) = self.get_submodel(&name) {
+                    for (mut names, param) in submodel.get_all_parameters() {
+                        names.insert(0, name.clone());
+                        params.insert(names, param);
+                    }
+                }
+            }
+            params
+        }
+
         // TODO(chantera): Implement get_trainable_parameters().
     }
 
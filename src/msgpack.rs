@@ -0,0 +1,179 @@
+//! Reader of the names of the statistics in the files written by `save()` of parameters and
+//! models.
+//!
+//! The core library writes the files in the MessagePack format. Each file begins with the major
+//! and the minor versions and the data type as unsigned integers. A parameter file continues
+//! with the value and the statistics of the parameter, and a model file continues with the
+//! number of parameters, followed by the name hierarchy, the value and the statistics of each
+//! parameter. A tensor is written as the array of dimensions, the batch size and the binary of
+//! the values, and statistics are written as a map from names to tensors.
+//!
+//! The core API does not enumerate the statistics, so these readers recover the names which are
+//! loaded from the files.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str;
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() - self.pos < len {
+            return Err(invalid_data("unexpected end of msgpack data".to_string()));
+        }
+        let bytes = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Reads a big-endian unsigned integer of `len` bytes.
+    fn read_be(&mut self, len: usize) -> io::Result<u64> {
+        Ok(self
+            .take(len)?
+            .iter()
+            .fold(0, |value, &byte| value << 8 | byte as u64))
+    }
+
+    fn read_marker(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn unexpected<T>(&self, marker: u8, expected: &str) -> io::Result<T> {
+        Err(invalid_data(format!(
+            "expected {} in msgpack data, but found marker 0x{:02x} at {}",
+            expected,
+            marker,
+            self.pos - 1
+        )))
+    }
+
+    fn read_uint_with(&mut self, marker: u8) -> io::Result<u64> {
+        match marker {
+            0x00..=0x7f => Ok(marker as u64),
+            0xcc => self.read_be(1),
+            0xcd => self.read_be(2),
+            0xce => self.read_be(4),
+            0xcf => self.read_be(8),
+            _ => self.unexpected(marker, "an unsigned integer"),
+        }
+    }
+
+    fn read_uint(&mut self) -> io::Result<u64> {
+        let marker = self.read_marker()?;
+        self.read_uint_with(marker)
+    }
+
+    fn read_array_len(&mut self) -> io::Result<u64> {
+        match self.read_marker()? {
+            marker @ 0x90..=0x9f => Ok((marker & 0x0f) as u64),
+            0xdc => self.read_be(2),
+            0xdd => self.read_be(4),
+            marker => self.unexpected(marker, "an array"),
+        }
+    }
+
+    fn read_map_len_with(&mut self, marker: u8) -> io::Result<u64> {
+        match marker {
+            0x80..=0x8f => Ok((marker & 0x0f) as u64),
+            0xde => self.read_be(2),
+            0xdf => self.read_be(4),
+            _ => self.unexpected(marker, "a map"),
+        }
+    }
+
+    fn read_str(&mut self) -> io::Result<String> {
+        let len = match self.read_marker()? {
+            marker @ 0xa0..=0xbf => (marker & 0x1f) as u64,
+            0xd9 => self.read_be(1)?,
+            0xda => self.read_be(2)?,
+            0xdb => self.read_be(4)?,
+            marker => return self.unexpected(marker, "a string"),
+        };
+        let bytes = self.take(len as usize)?;
+        str::from_utf8(bytes)
+            .map(|s| s.to_string())
+            .map_err(|_| invalid_data("invalid UTF-8 string in msgpack data".to_string()))
+    }
+
+    fn skip_bin(&mut self) -> io::Result<()> {
+        let len = match self.read_marker()? {
+            0xc4 => self.read_be(1)?,
+            0xc5 => self.read_be(2)?,
+            0xc6 => self.read_be(4)?,
+            marker => return self.unexpected(marker, "a binary"),
+        };
+        self.take(len as usize).map(|_| ())
+    }
+
+    fn skip_header(&mut self) -> io::Result<()> {
+        for _ in 0..3 {
+            self.read_uint()?;
+        }
+        Ok(())
+    }
+
+    fn skip_tensor(&mut self) -> io::Result<()> {
+        for _ in 0..self.read_array_len()? {
+            self.read_uint()?;
+        }
+        self.read_uint()?;
+        self.skip_bin()
+    }
+
+    fn read_stats_names(&mut self) -> io::Result<Vec<String>> {
+        let marker = self.read_marker()?;
+        let len = self.read_map_len_with(marker)?;
+        let mut names = vec![];
+        for _ in 0..len {
+            names.push(self.read_str()?);
+            self.skip_tensor()?;
+        }
+        Ok(names)
+    }
+}
+
+/// Reads the names of the statistics in a file written by `Parameter::save()`.
+pub(crate) fn read_parameter_stats_names<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>> {
+    let bytes = fs::read(path)?;
+    let mut reader = Reader::new(&bytes);
+    reader.skip_header()?;
+    reader.skip_tensor()?;
+    reader.read_stats_names()
+}
+
+/// Reads the names of the statistics of each parameter in a file written by `Model::save()`,
+/// with the name hierarchy of the parameter.
+pub(crate) fn read_model_stats_names<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<Vec<(Vec<String>, Vec<String>)>> {
+    let bytes = fs::read(path)?;
+    let mut reader = Reader::new(&bytes);
+    reader.skip_header()?;
+    // The number of parameters may be written as a map header or as an integer.
+    let marker = reader.read_marker()?;
+    let len = reader
+        .read_map_len_with(marker)
+        .or_else(|_| reader.read_uint_with(marker))?;
+    let mut params = vec![];
+    for _ in 0..len {
+        let names = (0..reader.read_array_len()?)
+            .map(|_| reader.read_str())
+            .collect::<io::Result<Vec<_>>>()?;
+        reader.skip_tensor()?;
+        params.push((names, reader.read_stats_names()?));
+    }
+    Ok(params)
+}
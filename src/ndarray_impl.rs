@@ -0,0 +1,99 @@
+//! Conversions between primitiv values and `ndarray` arrays.
+//!
+//! primitiv stores the values of each sample in column-major order, and the samples of a
+//! minibatch are placed one after another. An array is therefore converted into a shape whose
+//! dimensions are the (non-batch) axes of the array, and whose values are listed in Fortran
+//! order over the array with the batch axis moved to the end.
+
+use devices::AnyDevice;
+use functions::tensor_funcs;
+use ndarray::{ArrayD, IxDyn, ShapeBuilder};
+use Device;
+use Node;
+use Shape;
+use Tensor;
+
+/// Splits an array into a Shape and a list of values in primitiv's layout.
+///
+/// `batch_axis` specifies the axis of `array` which is regarded as the minibatch dimension.
+///
+/// Panics if `batch_axis` is out of range.
+pub(crate) fn to_shape_and_values(
+    array: ArrayD<f32>,
+    batch_axis: Option<usize>,
+) -> (Shape, Vec<f32>) {
+    let ndim = array.ndim();
+    let array = match batch_axis {
+        Some(axis) => {
+            assert!(
+                axis < ndim,
+                "batch axis {} is out of range for an array with {} dimensions",
+                axis,
+                ndim
+            );
+            let mut axes: Vec<usize> = (0..ndim).filter(|&i| i != axis).collect();
+            axes.push(axis);
+            array.permuted_axes(axes)
+        }
+        None => array,
+    };
+    let mut dims: Vec<u32> = array.shape().iter().map(|&d| d as u32).collect();
+    let batch = if batch_axis.is_some() {
+        dims.pop().unwrap()
+    } else {
+        1
+    };
+    // Iterating over the transposed view in logical order gives the Fortran order.
+    let values = array.t().iter().cloned().collect();
+    (Shape::from_dims(&dims, batch), values)
+}
+
+/// Builds an array from a Shape and a list of values in primitiv's layout.
+///
+/// The batch dimension is appended as the last axis if the shape has a minibatch.
+pub(crate) fn from_shape_and_values(shape: &Shape, values: Vec<f32>) -> ArrayD<f32> {
+    let mut dims: Vec<usize> = shape.dims().iter().map(|&d| d as usize).collect();
+    if shape.has_batch() {
+        dims.push(shape.batch() as usize);
+    }
+    ArrayD::from_shape_vec(IxDyn(&dims).f(), values).expect("number of values must match")
+}
+
+impl Tensor {
+    /// Creates a new Tensor object from an array on the default device.
+    ///
+    /// `batch_axis` specifies the axis of `array` which is regarded as the minibatch dimension.
+    /// If `None`, the tensor has no minibatch.
+    pub fn from_ndarray(array: ArrayD<f32>, batch_axis: Option<usize>) -> Self {
+        Self::from_ndarray_on::<AnyDevice>(array, batch_axis, None)
+    }
+
+    /// Creates a new Tensor object from an array on the specified device.
+    pub fn from_ndarray_on<D: Device>(
+        array: ArrayD<f32>,
+        batch_axis: Option<usize>,
+        dev: Option<&mut D>,
+    ) -> Self {
+        tensor_funcs::input_ndarray_on(array, batch_axis, dev)
+    }
+
+    /// Retrieves the values in the tensor as an array.
+    ///
+    /// The array has the axes of `shape().dims()`, followed by the minibatch axis if
+    /// `shape().has_batch()` is true.
+    ///
+    /// Remark: Trailing dimensions of size 1 are omitted by primitiv, so they do not appear in the
+    /// returned array.
+    pub fn to_ndarray(&self) -> ArrayD<f32> {
+        from_shape_and_values(&self.shape(), self.to_vector())
+    }
+}
+
+impl Node {
+    /// Calculates the value of this node and returns it as an array.
+    ///
+    /// See `Tensor::to_ndarray()` for the layout of the array.
+    pub fn to_ndarray(&self) -> ArrayD<f32> {
+        from_shape_and_values(&self.shape(), self.to_vector())
+    }
+}
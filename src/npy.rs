@@ -0,0 +1,431 @@
+//! Reader and writer of NumPy's `.npy` and `.npz` formats.
+//!
+//! Arrays are always written in the Fortran order, which is the layout of primitiv. The minibatch
+//! dimension, if any, is written as the last axis of the array.
+//!
+//! Only uncompressed `.npz` archives (e.g., created by `numpy.savez`) are supported.
+
+use devices::AnyDevice;
+use functions::tensor_funcs;
+use shape::MAX_DEPTH;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::u32;
+use Device;
+use Parameter;
+use Shape;
+use Tensor;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    bytes[0] as u16 | (bytes[1] as u16) << 8
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    read_u32(bytes) as u64 | (read_u32(&bytes[4..]) as u64) << 32
+}
+
+fn write_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&[value as u8, (value >> 8) as u8]);
+}
+
+fn write_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&[
+        value as u8,
+        (value >> 8) as u8,
+        (value >> 16) as u8,
+        (value >> 24) as u8,
+    ]);
+}
+
+/// Encodes the preamble and the header of the `.npy` format.
+fn encode_header(shape: &Shape, descr: &str, data_len: usize) -> Vec<u8> {
+    let mut dims: Vec<String> = shape.dims().iter().map(|d| d.to_string()).collect();
+    if shape.has_batch() {
+        dims.push(shape.batch().to_string());
+    }
+    let dims = match dims.len() {
+        1 => format!("({},)", dims[0]),
+        _ => format!("({})", dims.join(", ")),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': True, 'shape': {}, }}",
+        descr, dims
+    );
+    // The total length of the preamble and the header must be aligned to 64 bytes.
+    let preamble = MAGIC.len() + 4;
+    while (preamble + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let mut buffer = Vec::with_capacity(preamble + header.len() + data_len);
+    buffer.extend_from_slice(MAGIC);
+    buffer.extend_from_slice(&[1, 0]);
+    write_u16(&mut buffer, header.len() as u16);
+    buffer.extend_from_slice(header.as_bytes());
+    buffer
+}
+
+/// Encodes values of the given shape into the `.npy` format.
+pub(crate) fn encode_npy(shape: &Shape, values: &[f32]) -> Vec<u8> {
+    let mut buffer = encode_header(shape, "<f4", 4 * values.len());
+    for value in values {
+        write_u32(&mut buffer, value.to_bits());
+    }
+    buffer
+}
+
+/// Encodes 8-bit integers of the given shape into the `.npy` format.
+pub(crate) fn encode_npy_i8(shape: &Shape, values: &[i8]) -> Vec<u8> {
+    let mut buffer = encode_header(shape, "|i1", values.len());
+    buffer.extend(values.iter().map(|&value| value as u8));
+    buffer
+}
+
+/// Returns the value of `key` in the header dictionary.
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let pattern = format!("'{}':", key);
+    let start = header.find(&pattern)? + pattern.len();
+    let rest = header[start..].trim();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find(|c| c == ',' || c == '}')?
+    };
+    Some(rest[..end].trim())
+}
+
+/// Decodes an array in the `.npy` format.
+///
+/// `batch_axis` specifies the axis of the array which is regarded as the minibatch dimension.
+/// The returned values are in the column-major order with the minibatch at last.
+pub(crate) fn decode_npy(bytes: &[u8], batch_axis: Option<usize>) -> io::Result<(Shape, Vec<f32>)> {
+    if bytes.len() < MAGIC.len() + 4 || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(invalid_data("not an npy file".to_string()));
+    }
+    let major = bytes[MAGIC.len()];
+    let (header_len, header_start) = match major {
+        1 => (read_u16(&bytes[8..]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (read_u32(&bytes[8..]) as usize, 12),
+        _ => return Err(invalid_data(format!("unsupported npy version: {}", major))),
+    };
+    let data_start = header_start + header_len;
+    if bytes.len() < data_start {
+        return Err(invalid_data("truncated npy header".to_string()));
+    }
+    let header = String::from_utf8_lossy(&bytes[header_start..data_start]);
+
+    let descr = header_value(&header, "descr")
+        .ok_or_else(|| invalid_data("missing 'descr' in npy header".to_string()))?
+        .trim_matches(|c| c == '\'' || c == '"');
+    let fortran_order = match header_value(&header, "fortran_order") {
+        Some("True") => true,
+        Some("False") => false,
+        _ => {
+            return Err(invalid_data(
+                "invalid 'fortran_order' in npy header".to_string(),
+            ))
+        }
+    };
+    let shape = header_value(&header, "shape")
+        .ok_or_else(|| invalid_data("missing 'shape' in npy header".to_string()))?;
+    let shape = shape
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|d| d.trim())
+        .filter(|d| !d.is_empty())
+        .map(|d| d.parse::<usize>())
+        .collect::<::std::result::Result<Vec<_>, _>>()
+        .map_err(|_| invalid_data(format!("invalid shape in npy header: {}", shape)))?;
+    if shape.iter().any(|&d| d > u32::MAX as usize) {
+        return Err(invalid_data(format!(
+            "array of shape {:?} can not be represented by Shape",
+            shape
+        )));
+    }
+
+    let item_size = match descr {
+        "<f4" => 4usize,
+        "<f8" => 8,
+        "|i1" => 1,
+        _ => return Err(invalid_data(format!("unsupported npy dtype: {}", descr))),
+    };
+    let data = &bytes[data_start..];
+    let data_len = shape.iter().fold(Some(item_size), |len, &d| {
+        len.and_then(|len| len.checked_mul(d))
+    });
+    if data_len.map_or(true, |len| len > data.len()) {
+        return Err(invalid_data("truncated npy data".to_string()));
+    }
+    // This does not overflow since `data_len` does not.
+    let size = shape.iter().product::<usize>();
+    let values: Vec<f32> = match item_size {
+        4 => data
+            .chunks(4)
+            .take(size)
+            .map(|b| f32::from_bits(read_u32(b)))
+            .collect(),
+        8 => data
+            .chunks(8)
+            .take(size)
+            .map(|b| f64::from_bits(read_u64(b)) as f32)
+            .collect(),
+        _ => data[..size].iter().map(|&b| b as i8 as f32).collect(),
+    };
+
+    if let Some(axis) = batch_axis {
+        if axis >= shape.len() {
+            return Err(invalid_data(format!(
+                "batch axis {} is out of range for an array with {} dimensions",
+                axis,
+                shape.len()
+            )));
+        }
+    }
+    let depth = shape.len() - batch_axis.map(|_| 1).unwrap_or(0);
+    if depth > MAX_DEPTH || shape.contains(&0) {
+        return Err(invalid_data(format!(
+            "array of shape {:?} can not be represented by Shape",
+            shape
+        )));
+    }
+    let values = to_column_major(&shape, fortran_order, batch_axis, values);
+    let dims: Vec<u32> = (0..shape.len())
+        .filter(|&i| Some(i) != batch_axis)
+        .map(|i| shape[i] as u32)
+        .collect();
+    let batch = batch_axis.map(|axis| shape[axis] as u32).unwrap_or(1);
+    Ok((Shape::from_dims(&dims, batch), values))
+}
+
+/// Rearranges values into the column-major order with the batch axis moved to the last.
+fn to_column_major(
+    shape: &[usize],
+    fortran_order: bool,
+    batch_axis: Option<usize>,
+    values: Vec<f32>,
+) -> Vec<f32> {
+    let n = shape.len();
+    if fortran_order && batch_axis.map(|axis| axis + 1 == n).unwrap_or(true) {
+        return values;
+    }
+    let mut strides = vec![1usize; n];
+    if fortran_order {
+        for i in 1..n {
+            strides[i] = strides[i - 1] * shape[i - 1];
+        }
+    } else {
+        for i in (0..n.saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * shape[i + 1];
+        }
+    }
+    let mut axes: Vec<usize> = (0..n).filter(|&i| Some(i) != batch_axis).collect();
+    axes.extend(batch_axis);
+    let mut index = vec![0usize; n];
+    let mut retval = Vec::with_capacity(values.len());
+    for _ in 0..values.len() {
+        let offset: usize = (0..n).map(|k| index[k] * strides[axes[k]]).sum();
+        retval.push(values[offset]);
+        for k in 0..n {
+            index[k] += 1;
+            if index[k] < shape[axes[k]] {
+                break;
+            }
+            index[k] = 0;
+        }
+    }
+    retval
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Encodes entries into an uncompressed zip archive, which is the container of `.npz`.
+pub(crate) fn encode_npz(entries: &[(String, Vec<u8>)]) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![];
+    let mut central = vec![];
+    for (name, data) in entries {
+        let name = format!("{}.npy", name);
+        if buffer.len() > u32::max_value() as usize || data.len() > u32::max_value() as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "npz larger than 4 GiB is not supported",
+            ));
+        }
+        let offset = buffer.len() as u32;
+        let crc = crc32(data);
+        // Local file header.
+        write_u32(&mut buffer, 0x0403_4b50);
+        write_u16(&mut buffer, 20); // version needed to extract
+        write_u16(&mut buffer, 0); // flags
+        write_u16(&mut buffer, 0); // compression method (stored)
+        write_u16(&mut buffer, 0); // modification time
+        write_u16(&mut buffer, 0x21); // modification date (1980-01-01)
+        write_u32(&mut buffer, crc);
+        write_u32(&mut buffer, data.len() as u32);
+        write_u32(&mut buffer, data.len() as u32);
+        write_u16(&mut buffer, name.len() as u16);
+        write_u16(&mut buffer, 0); // extra field length
+        buffer.extend_from_slice(name.as_bytes());
+        buffer.extend_from_slice(data);
+        // Central directory header.
+        write_u32(&mut central, 0x0201_4b50);
+        write_u16(&mut central, 20); // version made by
+        write_u16(&mut central, 20); // version needed to extract
+        write_u16(&mut central, 0);
+        write_u16(&mut central, 0);
+        write_u16(&mut central, 0);
+        write_u16(&mut central, 0x21);
+        write_u32(&mut central, crc);
+        write_u32(&mut central, data.len() as u32);
+        write_u32(&mut central, data.len() as u32);
+        write_u16(&mut central, name.len() as u16);
+        write_u16(&mut central, 0); // extra field length
+        write_u16(&mut central, 0); // comment length
+        write_u16(&mut central, 0); // disk number
+        write_u16(&mut central, 0); // internal attributes
+        write_u32(&mut central, 0); // external attributes
+        write_u32(&mut central, offset);
+        central.extend_from_slice(name.as_bytes());
+    }
+    let central_offset = buffer.len() as u32;
+    let central_size = central.len() as u32;
+    buffer.extend_from_slice(&central);
+    // End of central directory record.
+    write_u32(&mut buffer, 0x0605_4b50);
+    write_u16(&mut buffer, 0);
+    write_u16(&mut buffer, 0);
+    write_u16(&mut buffer, entries.len() as u16);
+    write_u16(&mut buffer, entries.len() as u16);
+    write_u32(&mut buffer, central_size);
+    write_u32(&mut buffer, central_offset);
+    write_u16(&mut buffer, 0);
+    Ok(buffer)
+}
+
+/// Decodes entries of an uncompressed zip archive.
+///
+/// The `.npy` suffix is removed from the names of entries.
+pub(crate) fn decode_npz(bytes: &[u8]) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let truncated = || invalid_data("truncated npz file".to_string());
+    let eocd = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_u32(&bytes[i..]) == 0x0605_4b50)
+        .ok_or_else(|| invalid_data("not an npz file".to_string()))?;
+    let num_entries = read_u16(&bytes[eocd + 10..]) as usize;
+    let mut pos = read_u32(&bytes[eocd + 16..]) as usize;
+    let mut entries = Vec::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        if pos + 46 > bytes.len() || read_u32(&bytes[pos..]) != 0x0201_4b50 {
+            return Err(truncated());
+        }
+        let method = read_u16(&bytes[pos + 10..]);
+        let crc = read_u32(&bytes[pos + 16..]);
+        let size = read_u32(&bytes[pos + 20..]) as usize;
+        let name_len = read_u16(&bytes[pos + 28..]) as usize;
+        let extra_len = read_u16(&bytes[pos + 30..]) as usize;
+        let comment_len = read_u16(&bytes[pos + 32..]) as usize;
+        let offset = read_u32(&bytes[pos + 42..]) as usize;
+        if pos + 46 + name_len > bytes.len() {
+            return Err(truncated());
+        }
+        let name = String::from_utf8_lossy(&bytes[pos + 46..pos + 46 + name_len]).into_owned();
+        pos += 46 + name_len + extra_len + comment_len;
+
+        if method != 0 {
+            return Err(invalid_data(format!(
+                "compressed npz entry is not supported: {}",
+                name
+            )));
+        }
+        if offset + 30 > bytes.len() || read_u32(&bytes[offset..]) != 0x0403_4b50 {
+            return Err(truncated());
+        }
+        let data_start = offset
+            + 30
+            + read_u16(&bytes[offset + 26..]) as usize
+            + read_u16(&bytes[offset + 28..]) as usize;
+        if data_start + size > bytes.len() {
+            return Err(truncated());
+        }
+        let data = bytes[data_start..data_start + size].to_vec();
+        if crc32(&data) != crc {
+            return Err(invalid_data(format!("CRC mismatch in npz entry: {}", name)));
+        }
+        let name = if name.ends_with(".npy") {
+            name[..name.len() - 4].to_string()
+        } else {
+            name
+        };
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+pub(crate) fn read_file<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    File::open(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub(crate) fn write_file<P: AsRef<Path>>(path: P, bytes: &[u8]) -> io::Result<()> {
+    File::create(path)?.write_all(bytes)
+}
+
+impl Tensor {
+    /// Saves the values of the tensor into a `.npy` file.
+    ///
+    /// The array has the dimensions of `shape().dims()` in the Fortran order, followed by the
+    /// minibatch axis if `shape().has_batch()` is true.
+    pub fn save_npy<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        write_file(path, &encode_npy(&self.shape(), &self.to_vector()))
+    }
+
+    /// Loads a tensor from a `.npy` file onto the default device.
+    ///
+    /// `batch_axis` specifies the axis of the array which is regarded as the minibatch dimension.
+    /// `float32`, `float64` and `int8` arrays in any memory order are accepted.
+    pub fn load_npy<P: AsRef<Path>>(path: P, batch_axis: Option<usize>) -> io::Result<Self> {
+        Self::load_npy_on::<P, AnyDevice>(path, batch_axis, None)
+    }
+
+    /// Loads a tensor from a `.npy` file onto the specified device.
+    pub fn load_npy_on<P: AsRef<Path>, D: Device>(
+        path: P,
+        batch_axis: Option<usize>,
+        dev: Option<&mut D>,
+    ) -> io::Result<Self> {
+        let (shape, values) = decode_npy(&read_file(path)?, batch_axis)?;
+        Ok(tensor_funcs::input_on(shape, &values, dev))
+    }
+}
+
+impl Parameter {
+    /// Saves the current values of the parameter into a `.npy` file.
+    pub fn save_npy<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.value().save_npy(path)
+    }
+}
@@ -1,13 +1,209 @@
-use model_internal;
+use functions::tensor_funcs as F;
+use msgpack;
+use parameter;
 use primitiv_sys as _primitiv;
+use prune;
+use std::collections::HashMap;
+use std::env;
 use std::ffi::CString;
+use std::fs;
 use std::io;
 use std::path::Path;
+use std::process;
+use std::sync::Mutex;
 use ApiResult;
+use Device;
 use Model;
 use Parameter;
+use Shape;
+use Tensor;
 use Wrap;
 
+lazy_static! {
+    /// Addresses of the parameters registered to each optimizer, keyed by its address.
+    static ref OPTIMIZER_PARAMETERS: Mutex<HashMap<usize, Vec<usize>>> =
+        Mutex::new(HashMap::new());
+    /// Addresses of the registered parameters which are not passed to the core library yet
+    /// since they are frozen, keyed by the address of the optimizer.
+    static ref PENDING_PARAMETERS: Mutex<HashMap<usize, Vec<usize>>> =
+        Mutex::new(HashMap::new());
+    /// Names of the statistics which each optimizer adds to parameters, keyed by its address.
+    static ref OPTIMIZER_STATS_NAMES: Mutex<HashMap<usize, Vec<String>>> =
+        Mutex::new(HashMap::new());
+    /// Numbers of micro-batches accumulated by each optimizer, keyed by its address.
+    static ref ACCUMULATED_STEPS: Mutex<HashMap<usize, u32>> = Mutex::new(HashMap::new());
+}
+
+fn register_parameters<I: IntoIterator<Item = *const _primitiv::primitivParameter_t>>(
+    optimizer: *const _primitiv::primitivOptimizer_t,
+    params: I,
+) {
+    OPTIMIZER_PARAMETERS
+        .lock()
+        .unwrap()
+        .entry(optimizer as usize)
+        .or_insert_with(Vec::new)
+        .extend(params.into_iter().map(|param| param as usize));
+}
+
+/// Passes the parameters to the core optimizer, which adds its statistics to them.
+fn pass_to_core(optimizer: *mut _primitiv::primitivOptimizer_t, params: &mut [Parameter]) {
+    if params.is_empty() {
+        return;
+    }
+    unsafe {
+        let mut param_ptrs = params
+            .iter_mut()
+            .map(|param| param.as_mut_ptr())
+            .collect::<Vec<_>>();
+        check_api_status!(_primitiv::primitivAddParametersToOptimizer(
+            optimizer,
+            param_ptrs.as_mut_ptr(),
+            param_ptrs.len(),
+        ));
+    }
+}
+
+/// Passes the trainable parameters to the core optimizer, and holds the others until they become
+/// trainable.
+fn add_parameters_to_core(optimizer: *mut _primitiv::primitivOptimizer_t, params: &[Parameter]) {
+    let (mut trainable, frozen): (Vec<_>, Vec<_>) = params
+        .iter()
+        .map(|param| Parameter::from_raw(param.as_ptr() as *mut _, false))
+        .partition(|param| param.is_trainable());
+    pass_to_core(optimizer, &mut trainable);
+    PENDING_PARAMETERS
+        .lock()
+        .unwrap()
+        .entry(optimizer as usize)
+        .or_insert_with(Vec::new)
+        .extend(frozen.iter().map(|param| param.as_ptr() as usize));
+    register_parameters(optimizer, params.iter().map(|param| param.as_ptr()));
+}
+
+/// Returns the names of the statistics which the core optimizer of type `O` adds to parameters.
+///
+/// The core API does not enumerate the statistics, so a scalar parameter is passed to a new
+/// optimizer of the same type and saved to a temporary file, from which the names are read.
+fn probe_stats_names<O: Optimizer, D: Device>(device: &mut D) -> io::Result<Vec<String>> {
+    let param = Parameter::from_values_on(Shape::new(), &[0.0], Some(device));
+    let mut params = [param];
+    let mut optimizer = O::default();
+    pass_to_core(optimizer.as_mut_ptr(), &mut params);
+    let path = env::temp_dir().join(format!(
+        "primitiv_optimizer_stats_{}_{}.data",
+        process::id(),
+        optimizer.as_ptr() as usize
+    ));
+    params[0].save(&path, true)?;
+    let names = msgpack::read_parameter_stats_names(&path);
+    fs::remove_file(&path)?;
+    names
+}
+
+/// Records the names of the statistics which the optimizer adds to the parameters, probing them
+/// at the first call for each optimizer.
+fn record_stats_names<O: Optimizer>(optimizer: &O, params: &[Parameter]) {
+    let mut device = match params.iter().find(|param| param.valid()) {
+        Some(param) => param.device(),
+        None => return,
+    };
+    let probed = OPTIMIZER_STATS_NAMES
+        .lock()
+        .unwrap()
+        .get(&(optimizer.as_ptr() as usize))
+        .cloned();
+    // The lock is released before probing, since the probing optimizer unregisters itself.
+    let names = probed.unwrap_or_else(|| {
+        let names = probe_stats_names::<O, _>(&mut device).unwrap_or_default();
+        OPTIMIZER_STATS_NAMES
+            .lock()
+            .unwrap()
+            .insert(optimizer.as_ptr() as usize, names.clone());
+        names
+    });
+    for param in params {
+        param.record_stats_names(names.iter().cloned());
+    }
+}
+
+/// Returns the registered parameters which are not passed to the core optimizer yet.
+fn pending_parameters(optimizer: *const _primitiv::primitivOptimizer_t) -> Vec<Parameter> {
+    PENDING_PARAMETERS
+        .lock()
+        .unwrap()
+        .get(&(optimizer as usize))
+        .map(|params| {
+            params
+                .iter()
+                .map(|&param| Parameter::from_raw(param as *mut _, false))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Copies the value and the statistics of the parameter on its device.
+fn backup_parameter(param: &Parameter) -> (Tensor, Vec<(String, Tensor)>) {
+    let mut device = param.device();
+    let value = F::copy_on(param.value(), Some(&mut device));
+    let stats = param
+        .stats_names()
+        .into_iter()
+        .map(|name| {
+            let stats = F::copy_on(param.stats(&name), Some(&mut device));
+            (name, stats)
+        })
+        .collect();
+    (value, stats)
+}
+
+/// Writes back the value and the statistics copied by `backup_parameter()`.
+fn restore_parameter(param: &Parameter, backup: &(Tensor, Vec<(String, Tensor)>)) {
+    param.value().reset_by_tensor(&backup.0);
+    for &(ref name, ref stats) in &backup.1 {
+        param.stats(name).reset_by_tensor(stats);
+    }
+}
+
+pub(crate) fn unregister_optimizer(optimizer: *const _primitiv::primitivOptimizer_t) {
+    OPTIMIZER_PARAMETERS
+        .lock()
+        .unwrap()
+        .remove(&(optimizer as usize));
+    PENDING_PARAMETERS
+        .lock()
+        .unwrap()
+        .remove(&(optimizer as usize));
+    OPTIMIZER_STATS_NAMES
+        .lock()
+        .unwrap()
+        .remove(&(optimizer as usize));
+    ACCUMULATED_STEPS
+        .lock()
+        .unwrap()
+        .remove(&(optimizer as usize));
+}
+
+/// Returns the parameters registered to the optimizer.
+///
+/// The returned objects do not own the parameters, which are owned by the caller of
+/// `add_parameter` or `add_model` as in the core library.
+pub(crate) fn registered_parameters(
+    optimizer: *const _primitiv::primitivOptimizer_t,
+) -> Vec<Parameter> {
+    OPTIMIZER_PARAMETERS
+        .lock()
+        .unwrap()
+        .get(&(optimizer as usize))
+        .map(|params| {
+            params
+                .iter()
+                .map(|&param| Parameter::from_raw(param as *mut _, false))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// `Optimizer` trait
 pub trait Optimizer: Wrap<_primitiv::primitivOptimizer_t> + Default {
     /// Loads configurations from a file.
@@ -122,84 +318,167 @@ pub trait Optimizer: Wrap<_primitiv::primitivOptimizer_t> + Default {
         }
     }
 
+    /// Scales the gradients of registered parameters so that their global L2 norm does not
+    /// exceed `max_norm`, and returns the norm before scaling.
+    ///
+    /// Parameters which are not trainable are not taken into account. Unlike
+    /// `set_gradient_clipping()`, this does not clip each parameter independently.
+    fn clip_grad_norm(&mut self, max_norm: f32) -> f32 {
+        let params = registered_parameters(self.as_ptr())
+            .into_iter()
+            .filter(|param| param.is_trainable())
+            .collect::<Vec<_>>();
+        let norm = parameter::gradient_norm(&params);
+        if norm > max_norm {
+            for param in &params {
+                param.gradient().inplace_multiply_const(max_norm / norm);
+            }
+        }
+        norm
+    }
+
     /// Registers a parameter.
+    ///
+    /// Parameters which are not trainable are passed to the core library when they become
+    /// trainable.
+    ///
+    /// Remark: To record the names of the statistics which this optimizer adds to parameters
+    /// (see `Parameter::stats_names()`), a scalar parameter is passed to a new optimizer of the
+    /// same type and saved to a temporary file when parameters are added for the first time.
     fn add_parameter(&mut self, param: &mut Parameter) {
-        unsafe {
-            check_api_status!(_primitiv::primitivAddParameterToOptimizer(
-                self.as_mut_ptr(),
-                param.as_mut_ptr(),
-            ));
-        }
+        let params = [Parameter::from_raw(param.as_mut_ptr(), false)];
+        record_stats_names(self, &params);
+        add_parameters_to_core(self.as_mut_ptr(), &params);
     }
 
     /// Registers multiple parameters.
     fn add_parameters(&mut self, params: &mut [Parameter]) {
-        unsafe {
-            let mut param_ptrs = params
-                .iter_mut()
-                .map(|param| param.as_mut_ptr())
-                .collect::<Vec<_>>();
-            check_api_status!(_primitiv::primitivAddParametersToOptimizer(
-                self.as_mut_ptr(),
-                param_ptrs.as_mut_ptr(),
-                param_ptrs.len(),
-            ));
-        }
+        record_stats_names(self, params);
+        add_parameters_to_core(self.as_mut_ptr(), params);
     }
 
     /// Registers a model.
     fn add_model<M: Model>(&mut self, model: &mut M) {
-        unsafe {
-            model.register_parameters();
-            let lock = model_internal::get_entity_mut(model);
-            let mut entity = lock.write().unwrap();
-            check_api_status!(_primitiv::primitivAddModelToOptimizer(
-                self.as_mut_ptr(),
-                entity.as_mut_ptr(),
-            ));
-        }
+        model.register_parameters();
+        let params = model
+            .get_all_parameters()
+            .into_iter()
+            .map(|(_, param)| param)
+            .collect::<Vec<_>>();
+        record_stats_names(self, &params);
+        add_parameters_to_core(self.as_mut_ptr(), &params);
     }
 
     /// Registers multiple models.
     fn add_models<M: Model>(&mut self, models: &mut [M]) {
-        unsafe {
-            let locks = models
-                .iter_mut()
-                .map(|model| {
-                    model.register_parameters();
-                    model_internal::get_entity_mut(model)
-                })
-                .collect::<Vec<_>>();
-            let mut guards = locks
-                .iter()
-                .map(|lock| lock.write().unwrap())
-                .collect::<Vec<_>>();
-            let mut model_ptrs = guards
-                .iter_mut()
-                .map(|entity| entity.as_mut_ptr())
-                .collect::<Vec<_>>();
-            check_api_status!(_primitiv::primitivAddModelsToOptimizer(
-                self.as_mut_ptr(),
-                model_ptrs.as_mut_ptr(),
-                model_ptrs.len(),
-            ));
+        for model in models {
+            self.add_model(model);
         }
     }
 
     /// Resets all gradients of registered parameters.
+    ///
+    /// This also resets the number of accumulated micro-batches.
     fn reset_gradients(&mut self) {
         unsafe {
             check_api_status!(_primitiv::primitivResetOptimizerGradients(
                 self.as_mut_ptr(),
             ));
         }
+        for mut param in pending_parameters(self.as_ptr()) {
+            param.reset_gradient();
+        }
+        ACCUMULATED_STEPS
+            .lock()
+            .unwrap()
+            .remove(&(self.as_ptr() as usize));
+    }
+
+    /// Counts a micro-batch whose gradients are accumulated by `backward()`.
+    ///
+    /// Call this after each backward pass, and `update_accumulated()` after the last one.
+    fn accumulate(&mut self) {
+        *ACCUMULATED_STEPS
+            .lock()
+            .unwrap()
+            .entry(self.as_ptr() as usize)
+            .or_insert(0) += 1;
+    }
+
+    /// Retrieves the number of micro-batches accumulated since the last reset of the
+    /// gradients.
+    fn get_accumulated_steps(&self) -> u32 {
+        ACCUMULATED_STEPS
+            .lock()
+            .unwrap()
+            .get(&(self.as_ptr() as usize))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Updates parameter values with the gradients averaged over the accumulated micro-batches,
+    /// and resets the gradients.
+    ///
+    /// Panics if no micro-batch has been accumulated.
+    fn update_accumulated(&mut self) {
+        let steps = self.get_accumulated_steps();
+        assert!(steps > 0, "no micro-batch has been accumulated");
+        if steps > 1 {
+            for param in registered_parameters(self.as_ptr()) {
+                param.gradient().inplace_multiply_const(1.0 / steps as f32);
+            }
+        }
+        self.update();
+        self.reset_gradients();
     }
 
     /// Updates parameter values.
+    ///
+    /// Parameters which became trainable since the last update are passed to the core library
+    /// beforehand, and parameters which are not trainable are left untouched. The constraints
+    /// and the pruning masks of trainable parameters are applied after the update.
+    ///
+    /// Remark: The core library updates all parameters passed to it, so the values and the
+    /// statistics of the parameters frozen after they were passed are copied on their devices
+    /// before the update and written back after it.
     fn update(&mut self) {
+        let (mut trainable, frozen): (Vec<_>, Vec<_>) = pending_parameters(self.as_ptr())
+            .into_iter()
+            .partition(|param| param.is_trainable());
+        if !trainable.is_empty() {
+            PENDING_PARAMETERS.lock().unwrap().insert(
+                self.as_ptr() as usize,
+                frozen.iter().map(|param| param.as_ptr() as usize).collect(),
+            );
+            pass_to_core(self.as_mut_ptr(), &mut trainable);
+        }
+        let pending = PENDING_PARAMETERS
+            .lock()
+            .unwrap()
+            .get(&(self.as_ptr() as usize))
+            .cloned()
+            .unwrap_or_default();
+        let backups = registered_parameters(self.as_ptr())
+            .into_iter()
+            .filter(|param| !param.is_trainable() && !pending.contains(&(param.as_ptr() as usize)))
+            .map(|param| {
+                let backup = backup_parameter(&param);
+                (param, backup)
+            })
+            .collect::<Vec<_>>();
         unsafe {
             check_api_status!(_primitiv::primitivExecuteOptimizerUpdate(self.as_mut_ptr()));
         }
+        for &(ref param, ref backup) in &backups {
+            restore_parameter(param, backup);
+        }
+        for mut param in registered_parameters(self.as_ptr())
+            .into_iter()
+            .filter(|param| param.is_trainable())
+        {
+            param.apply_constraints();
+            prune::apply_mask(&mut param);
+        }
     }
 
     /// Gets a configuration value.
@@ -262,7 +541,14 @@ pub trait Optimizer: Wrap<_primitiv::primitivOptimizer_t> + Default {
 macro_rules! impl_optimizer {
     ($name:ident) => {
         impl_wrap_owned!($name, primitivOptimizer_t);
-        impl_drop!($name, primitivDeleteOptimizer);
+        impl Drop for $name {
+            fn drop(&mut self) {
+                ::optimizer::unregister_optimizer(self.as_ptr());
+                unsafe {
+                    check_api_status!(_primitiv::primitivDeleteOptimizer(self.as_mut_ptr()));
+                }
+            }
+        }
         impl Optimizer for $name {}
     };
 }
@@ -1,25 +1,99 @@
 use devices::AnyDevice;
+use functions::tensor_funcs as F;
+use msgpack;
 use primitiv_sys as _primitiv;
+use shape::RawShape;
+use std::collections::{BTreeSet, HashMap};
 use std::ffi::CString;
 use std::io;
 use std::path::Path;
 use std::ptr::{self, NonNull};
+use std::sync::Mutex;
+use std::vec;
 use ApiResult;
+use Constraint;
 use Device;
 use Initializer;
 use Shape;
 use Tensor;
 use Wrap;
 
+/// Name of the statistics which holds 1 if the parameter is frozen and 0 otherwise.
+pub(crate) const FROZEN_STATS: &str = "frozen";
+
+lazy_static! {
+    /// Names of the statistics added to each parameter, keyed by its address.
+    static ref STATS_NAMES: Mutex<HashMap<usize, BTreeSet<String>>> = Mutex::new(HashMap::new());
+    /// Constraints attached to each parameter, keyed by its address.
+    static ref CONSTRAINTS: Mutex<HashMap<usize, Vec<Box<dyn Constraint>>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Returns the L2 norm of the gradients of the valid parameters, as if they were concatenated
+/// into one vector.
+pub(crate) fn gradient_norm<'a, I: IntoIterator<Item = &'a Parameter>>(params: I) -> f32 {
+    params
+        .into_iter()
+        .filter(|param| param.valid())
+        .map(|param| {
+            let grad = F::flatten(param.gradient());
+            F::batch::sum(F::sum(&grad * &grad, 0)).to_float()
+        })
+        .sum::<f32>()
+        .sqrt()
+}
+
 /// Struct to manage a trainable tensor parameter.
 #[derive(Debug)]
 pub struct Parameter {
     inner: NonNull<_primitiv::primitivParameter_t>,
     owned: bool,
+    shared: bool,
 }
 
-impl_wrap!(Parameter, primitivParameter_t);
-impl_drop!(Parameter, primitivDeleteParameter);
+impl Wrap<_primitiv::primitivParameter_t> for Parameter {
+    #[inline(always)]
+    fn from_raw(ptr: *mut _primitiv::primitivParameter_t, owned: bool) -> Self {
+        Parameter {
+            inner: NonNull::new(ptr).expect("pointer must not be null"),
+            owned,
+            shared: false,
+        }
+    }
+
+    #[inline(always)]
+    fn as_ptr(&self) -> *const _primitiv::primitivParameter_t {
+        self.inner.as_ptr()
+    }
+
+    #[inline(always)]
+    fn as_mut_ptr(&mut self) -> *mut _primitiv::primitivParameter_t {
+        self.inner.as_ptr()
+    }
+
+    #[inline(always)]
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+}
+
+impl Drop for Parameter {
+    fn drop(&mut self) {
+        if self.is_owned() {
+            STATS_NAMES
+                .lock()
+                .unwrap()
+                .remove(&(self.as_ptr() as usize));
+            CONSTRAINTS
+                .lock()
+                .unwrap()
+                .remove(&(self.as_ptr() as usize));
+            unsafe {
+                check_api_status!(_primitiv::primitivDeleteParameter(self.as_mut_ptr()));
+            }
+        }
+    }
+}
 
 impl Parameter {
     /// Creates an invalid parameter object.
@@ -45,7 +119,7 @@ impl Parameter {
         unsafe {
             let mut parameter_ptr: *mut _primitiv::primitivParameter_t = ptr::null_mut();
             check_api_status!(_primitiv::primitivCreateParameterWithValues(
-                shape.into().as_ptr(),
+                shape.into().to_raw().as_ptr(),
                 value.as_ptr(),
                 value.len(),
                 device.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
@@ -69,7 +143,7 @@ impl Parameter {
         unsafe {
             let mut parameter_ptr: *mut _primitiv::primitivParameter_t = ptr::null_mut();
             check_api_status!(_primitiv::primitivCreateParameterWithInitializer(
-                shape.into().as_ptr(),
+                shape.into().to_raw().as_ptr(),
                 initializer.as_ptr(),
                 device.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
                 &mut parameter_ptr,
@@ -93,7 +167,7 @@ impl Parameter {
         unsafe {
             check_api_status!(_primitiv::primitivInitializeParameterWithValues(
                 self.as_mut_ptr(),
-                shape.into().as_ptr(),
+                shape.into().to_raw().as_ptr(),
                 value.as_ptr(),
                 value.len(),
                 device.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
@@ -120,7 +194,7 @@ impl Parameter {
         unsafe {
             check_api_status!(_primitiv::primitivInitializeParameterWithInitializer(
                 self.as_mut_ptr(),
-                shape.into().as_ptr(),
+                shape.into().to_raw().as_ptr(),
                 initializer.as_ptr(),
                 device.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
             ));
@@ -150,8 +224,12 @@ impl Parameter {
                     device.map(|d| d.as_mut_ptr()).unwrap_or(ptr::null_mut()),
                 ),
                 (),
-            ).map_err(|status| io::Error::new(io::ErrorKind::Other, status.message()))
+            ).map_err(|status| io::Error::new(io::ErrorKind::Other, status.message()))?;
+        }
+        if with_stats {
+            self.record_stats_names(msgpack::read_parameter_stats_names(path)?);
         }
+        Ok(())
     }
 
     /// Saves current parameters into specified file.
@@ -166,6 +244,32 @@ impl Parameter {
         }
     }
 
+    /// Creates a handle which shares the value, the gradient and the statistics with this
+    /// parameter, e.g. to tie the input embeddings to the output weights.
+    ///
+    /// When the handle is registered to a model, the model refers to this parameter instead of
+    /// holding another one: it is saved, loaded and updated by optimizers only through this
+    /// parameter, and `get_all_parameters()` does not list it. `find_parameter()` resolves the
+    /// name of the handle as usual.
+    ///
+    /// # Safety
+    ///
+    /// The handle does not own the parameter and is not bound to the lifetime of this object.
+    /// The caller must ensure that the handle, and any model it is registered to, is not used
+    /// after this parameter is dropped, e.g. by keeping both in the same model.
+    pub unsafe fn share(&self) -> Parameter {
+        Parameter {
+            inner: self.inner,
+            owned: false,
+            shared: true,
+        }
+    }
+
+    /// Checks whether the object is a handle created by `share()` or not.
+    pub fn is_shared(&self) -> bool {
+        self.shared
+    }
+
     /// Returns whether the parameter is valid or not.
     pub fn valid(&self) -> bool {
         unsafe {
@@ -195,9 +299,58 @@ impl Parameter {
             check_api_status!(_primitiv::primitivAddStatsToParameter(
                 self.as_mut_ptr(),
                 name_ptr,
-                shape.into().as_ptr(),
+                shape.into().to_raw().as_ptr(),
             ));
         }
+        STATS_NAMES
+            .lock()
+            .unwrap()
+            .entry(self.as_ptr() as usize)
+            .or_insert_with(BTreeSet::new)
+            .insert(name.to_string());
+    }
+
+    /// Records the names of the statistics added without `add_stats()`, e.g. loaded from a file.
+    pub(crate) fn record_stats_names<I: IntoIterator<Item = String>>(&self, names: I) {
+        STATS_NAMES
+            .lock()
+            .unwrap()
+            .entry(self.as_ptr() as usize)
+            .or_insert_with(BTreeSet::new)
+            .extend(names);
+    }
+
+    /// Returns the names of the statistics which the parameter has, in the sorted order.
+    ///
+    /// Remark: The core library does not enumerate the statistics, so the names are recorded
+    /// when the statistics are added by `add_stats()`, `load()` of this object or the models
+    /// which hold it, and `Optimizer::add_parameter()` and its variants. Statistics added by
+    /// other means, e.g. by the core library called directly, are not listed, so the list can
+    /// be incomplete.
+    pub fn stats_names(&self) -> Vec<String> {
+        STATS_NAMES
+            .lock()
+            .unwrap()
+            .get(&(self.as_ptr() as usize))
+            .map(|names| {
+                names
+                    .iter()
+                    .filter(|name| self.has_stats(name))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns an iterator over the names and the tensors of the statistics.
+    ///
+    /// The tensors share the memory with the parameter, so they can be used to overwrite the
+    /// statistics. See `stats_names()` for the names to be listed.
+    pub fn stats_iter(&self) -> StatsIter {
+        StatsIter {
+            param: self,
+            names: self.stats_names().into_iter(),
+        }
     }
 
     /// Checks whether the statistics with name `name` exists or not.
@@ -215,6 +368,62 @@ impl Parameter {
         }
     }
 
+    /// Sets whether the parameter is updated by optimizers.
+    ///
+    /// `Optimizer::update()` leaves the values and the statistics of frozen parameters untouched,
+    /// so parameters can be frozen and unfrozen at any time, e.g. between phases of fine-tuning.
+    ///
+    /// The flag is stored as the scalar statistics named `"frozen"`, so it is saved and loaded
+    /// with the parameter by `save(path, true)` and `load(path, true)`.
+    pub fn set_trainable(&mut self, trainable: bool) {
+        if !self.has_stats(FROZEN_STATS) {
+            if trainable {
+                return;
+            }
+            self.add_stats(FROZEN_STATS, Shape::new());
+        }
+        self.stats(FROZEN_STATS)
+            .reset(if trainable { 0.0 } else { 1.0 });
+    }
+
+    /// Checks whether the parameter is updated by optimizers or not.
+    ///
+    /// Parameters are trainable unless `set_trainable(false)` is called.
+    pub fn is_trainable(&self) -> bool {
+        !self.has_stats(FROZEN_STATS) || self.stats(FROZEN_STATS).to_float() == 0.0
+    }
+
+    /// Attaches a constraint, which is applied after each update by optimizers.
+    ///
+    /// Constraints are applied in the order of attachment. They are not saved with the
+    /// parameter.
+    pub fn add_constraint<C: Constraint + 'static>(&mut self, constraint: C) {
+        CONSTRAINTS
+            .lock()
+            .unwrap()
+            .entry(self.as_ptr() as usize)
+            .or_insert_with(Vec::new)
+            .push(Box::new(constraint));
+    }
+
+    /// Detaches all constraints.
+    pub fn clear_constraints(&mut self) {
+        CONSTRAINTS
+            .lock()
+            .unwrap()
+            .remove(&(self.as_ptr() as usize));
+    }
+
+    /// Applies the attached constraints to the value.
+    pub fn apply_constraints(&mut self) {
+        if let Some(constraints) = CONSTRAINTS.lock().unwrap().get(&(self.as_ptr() as usize)) {
+            let mut value = self.value();
+            for constraint in constraints {
+                constraint.apply(&mut value);
+            }
+        }
+    }
+
     /// Returns the shape of the parameter.
     pub fn shape(&self) -> Shape {
         unsafe {
@@ -223,7 +432,7 @@ impl Parameter {
                 self.as_ptr(),
                 &mut shape_ptr,
             ));
-            Shape::from_raw(shape_ptr, true)
+            RawShape::from_raw(shape_ptr, true).to_shape()
         }
     }
 
@@ -279,6 +488,30 @@ impl Parameter {
     }
 }
 
+/// Iterator over the statistics of a parameter.
+///
+/// This is created by `Parameter::stats_iter()`.
+#[derive(Debug)]
+pub struct StatsIter<'a> {
+    param: &'a Parameter,
+    names: vec::IntoIter<String>,
+}
+
+impl<'a> Iterator for StatsIter<'a> {
+    type Item = (String, Tensor);
+
+    fn next(&mut self) -> Option<(String, Tensor)> {
+        self.names.next().map(|name| {
+            let stats = self.param.stats(&name);
+            (name, stats)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.names.size_hint()
+    }
+}
+
 impl Default for Parameter {
     fn default() -> Parameter {
         Parameter::new()
@@ -0,0 +1,109 @@
+//! Magnitude pruning of parameters.
+//!
+//! Pruning masks are stored as the statistics named `MASK_STATS` with the same shape as the
+//! values, so they are saved and loaded with the parameters by `save(path, true)` and
+//! `load(path, true)`. `Optimizer::update()` re-applies the masks of trainable parameters after
+//! each update, so that pruned weights stay 0 during training.
+//!
+//! Remark: The core library has no in-place elementwise multiplication, so applying a mask
+//! creates a temporary tensor of the same size as the parameter on its device at each update.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! prune::magnitude(&mut model, 0.5);
+//! for (names, sparsity) in prune::sparsity(&mut model) {
+//!     println!("{}: {}", names.join("/"), sparsity);
+//! }
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use Model;
+use Parameter;
+
+/// Name of the statistics which holds the pruning mask.
+pub const MASK_STATS: &str = "prune-mask";
+
+/// Returns the ratio of zeros in the pruning mask of the parameter.
+fn mask_sparsity(param: &Parameter) -> f32 {
+    let mask = param.stats(MASK_STATS).to_vector();
+    if mask.is_empty() {
+        return 0.0;
+    }
+    mask.iter().filter(|&&m| m == 0.0).count() as f32 / mask.len() as f32
+}
+
+/// Multiplies the value of the parameter by its pruning mask, if any.
+///
+/// This creates a temporary tensor for the product (see `Tensor::inplace_multiply()`).
+pub(crate) fn apply_mask(param: &mut Parameter) {
+    if param.has_stats(MASK_STATS) {
+        let mask = param.stats(MASK_STATS);
+        param.value().inplace_multiply(&mask);
+    }
+}
+
+/// Prunes the weights of the parameter with the smallest absolute values, so that the ratio of
+/// pruned weights becomes `sparsity`, and returns the resulting ratio.
+///
+/// Weights pruned before are kept pruned, so the result may exceed `sparsity`.
+pub fn magnitude_parameter(param: &mut Parameter, sparsity: f32) -> f32 {
+    assert!(
+        sparsity >= 0.0 && sparsity <= 1.0,
+        "sparsity must be in [0, 1]: {}",
+        sparsity
+    );
+    if !param.has_stats(MASK_STATS) {
+        let shape = param.shape();
+        param.add_stats(MASK_STATS, shape);
+        param.stats(MASK_STATS).reset(1.0);
+    }
+    let values = param.value().to_vector();
+    let mut mask = param.stats(MASK_STATS).to_vector();
+    let num_pruned = ((sparsity * values.len() as f32).round() as usize).min(values.len());
+    let mut order = (0..values.len()).collect::<Vec<_>>();
+    // Previously pruned weights come first, followed by the others in the ascending order.
+    order.sort_by(|&a, &b| {
+        (mask[a] != 0.0).cmp(&(mask[b] != 0.0)).then_with(|| {
+            values[a]
+                .abs()
+                .partial_cmp(&values[b].abs())
+                .unwrap_or(Ordering::Equal)
+        })
+    });
+    for &i in &order[..num_pruned] {
+        mask[i] = 0.0;
+    }
+    param.stats(MASK_STATS).reset_by_slice(&mask);
+    apply_mask(param);
+    mask_sparsity(param)
+}
+
+/// Prunes each valid weight matrix or higher-order parameter in the model and its submodels by
+/// `magnitude_parameter()`, and returns the resulting ratios keyed by the name hierarchies of the
+/// parameters.
+///
+/// Parameters with less than 2 dimensions, e.g. biases, are not pruned. Use
+/// `magnitude_parameter()` to prune them explicitly.
+pub fn magnitude<M: Model>(model: &mut M, sparsity: f32) -> BTreeMap<Vec<String>, f32> {
+    model.register_parameters();
+    model
+        .get_all_parameters()
+        .into_iter()
+        .filter(|&(_, ref param)| param.valid() && param.shape().depth() >= 2)
+        .map(|(names, mut param)| (names, magnitude_parameter(&mut param, sparsity)))
+        .collect()
+}
+
+/// Returns the ratios of pruned weights of the pruned parameters in the model and its
+/// submodels, keyed by their name hierarchies.
+pub fn sparsity<M: Model>(model: &mut M) -> BTreeMap<Vec<String>, f32> {
+    model.register_parameters();
+    model
+        .get_all_parameters()
+        .into_iter()
+        .filter(|&(_, ref param)| param.valid() && param.has_stats(MASK_STATS))
+        .map(|(names, param)| (names, mask_sparsity(&param)))
+        .collect()
+}
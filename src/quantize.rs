@@ -0,0 +1,306 @@
+//! Post-training quantization of parameters into 8-bit integers.
+//!
+//! Each value `x` is stored as `round(x / scale)` in the range `[-127, 127]`, where `scale` is
+//! the maximum absolute value divided by 127. The scale is computed for the whole tensor, or for
+//! each row, i.e., each index of the first dimension.
+//!
+//! A `QuantizedModel` is saved as an uncompressed `.npz` file, which holds an `int8` array named
+//! `<names>.q` and a `float32` array of the scales named `<names>.scale` for each parameter, where
+//! `<names>` is the name hierarchy of the parameter joined with `/` as in `Model::save_npz()`.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! QuantizedModel::new(&mut model, Granularity::PerRow).save("model.q.npz")?;
+//!
+//! let mut model = MyModel::new();
+//! QuantizedModel::load("model.q.npz")?.dequantize_into(&mut model)?;
+//! ```
+
+use devices::AnyDevice;
+use functions::tensor_funcs as F;
+use npy;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use Device;
+use Model;
+use Shape;
+use Tensor;
+
+/// Maximum absolute value of the quantized integers.
+const MAX_LEVEL: f32 = 127.0;
+const VALUES_SUFFIX: &str = ".q";
+const SCALES_SUFFIX: &str = ".scale";
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn quantize_value(x: f32, scale: f32) -> i8 {
+    if scale > 0.0 {
+        (x / scale).round().max(-MAX_LEVEL).min(MAX_LEVEL) as i8
+    } else {
+        0
+    }
+}
+
+/// Unit of values which share a scale.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Granularity {
+    /// One scale for the whole tensor.
+    PerTensor,
+    /// One scale for each index of the first dimension.
+    PerRow,
+}
+
+/// Tensor quantized into 8-bit integers.
+#[derive(Clone, Debug)]
+pub struct QuantizedTensor {
+    shape: Shape,
+    values: Vec<i8>,
+    scales: Vec<f32>,
+}
+
+impl QuantizedTensor {
+    /// Quantizes the values of a tensor.
+    pub fn new(x: &Tensor, granularity: Granularity) -> Self {
+        let shape = x.shape();
+        let values = x.to_vector();
+        let rows = match granularity {
+            Granularity::PerTensor => 1,
+            Granularity::PerRow => shape[0] as usize,
+        };
+        let mut scales = vec![0.0f32; rows];
+        for (i, value) in values.iter().enumerate() {
+            scales[i % rows] = scales[i % rows].max(value.abs());
+        }
+        for scale in &mut scales {
+            *scale /= MAX_LEVEL;
+        }
+        let values = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| quantize_value(value, scales[i % rows]))
+            .collect();
+        QuantizedTensor {
+            shape,
+            values,
+            scales,
+        }
+    }
+
+    fn from_parts(shape: Shape, values: Vec<i8>, scales: Vec<f32>) -> Option<Self> {
+        if values.len() == shape.size() && (scales.len() == 1 || scales.len() == shape[0] as usize)
+        {
+            Some(QuantizedTensor {
+                shape,
+                values,
+                scales,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the shape of the tensor.
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// Returns the quantized values in the column-major order.
+    pub fn values(&self) -> &[i8] {
+        &self.values
+    }
+
+    /// Returns the scales, which has one element for `Granularity::PerTensor`.
+    pub fn scales(&self) -> &[f32] {
+        &self.scales
+    }
+
+    /// Returns the approximated values in the column-major order.
+    pub fn dequantize(&self) -> Vec<f32> {
+        let rows = self.scales.len();
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| value as f32 * self.scales[i % rows])
+            .collect()
+    }
+
+    /// Creates a tensor of the approximated values on the default device.
+    pub fn to_tensor(&self) -> Tensor {
+        self.to_tensor_on::<AnyDevice>(None)
+    }
+
+    /// Creates a tensor of the approximated values on the specified device.
+    pub fn to_tensor_on<D: Device>(&self, device: Option<&mut D>) -> Tensor {
+        F::input_on(self.shape, &self.dequantize(), device)
+    }
+
+    /// Multiplies the quantized matrix and `x` on the host, without dequantizing the matrix.
+    ///
+    /// The result is created on the device of `x`.
+    pub fn matmul(&self, x: &Tensor) -> Tensor {
+        let x_shape = x.shape();
+        assert!(
+            self.shape.is_matrix() && !self.shape.has_batch(),
+            "quantized tensor must be a matrix: {}",
+            self.shape
+        );
+        assert!(
+            x_shape.is_matrix() && x_shape[0] == self.shape[1],
+            "can not multiply {} and {}",
+            self.shape,
+            x_shape
+        );
+        let (m, k) = (self.shape[0] as usize, self.shape[1] as usize);
+        let n = x_shape[1] as usize * x_shape.batch() as usize;
+        let rows = self.scales.len();
+        let xs = x.to_vector();
+        let mut ys = vec![0.0; m * n];
+        for (x_col, y_col) in xs.chunks(k).zip(ys.chunks_mut(m)) {
+            for (w_col, &x_value) in self.values.chunks(m).zip(x_col) {
+                for (y, &w) in y_col.iter_mut().zip(w_col) {
+                    *y += w as f32 * x_value;
+                }
+            }
+            for (i, y) in y_col.iter_mut().enumerate() {
+                *y *= self.scales[i % rows];
+            }
+        }
+        let y_shape = Shape::from_dims(&[self.shape[0], x_shape[1]], x_shape.batch());
+        F::input_on(y_shape, &ys, Some(&mut x.device()))
+    }
+}
+
+/// Quantized parameters of a model.
+#[derive(Clone, Debug, Default)]
+pub struct QuantizedModel {
+    params: BTreeMap<Vec<String>, QuantizedTensor>,
+}
+
+impl QuantizedModel {
+    /// Quantizes all valid parameters in the model and its submodels.
+    pub fn new<M: Model>(model: &mut M, granularity: Granularity) -> Self {
+        model.register_parameters();
+        let params = model
+            .get_all_parameters()
+            .into_iter()
+            .filter(|&(_, ref param)| param.valid())
+            .map(|(names, param)| (names, QuantizedTensor::new(&param.value(), granularity)))
+            .collect();
+        QuantizedModel { params }
+    }
+
+    /// Retrieves a quantized parameter with specified name hierarchy.
+    pub fn get(&self, names: &[&str]) -> Option<&QuantizedTensor> {
+        let names = names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        self.params.get(&names)
+    }
+
+    /// Returns all quantized parameters keyed by their name hierarchies.
+    pub fn parameters(&self) -> &BTreeMap<Vec<String>, QuantizedTensor> {
+        &self.params
+    }
+
+    /// Saves the quantized parameters to a `.npz` file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut entries = vec![];
+        for (names, param) in &self.params {
+            let name = names.join("/");
+            let scales_shape = Shape::from_dims(&[param.scales.len() as u32], 1);
+            entries.push((
+                format!("{}{}", name, VALUES_SUFFIX),
+                npy::encode_npy_i8(&param.shape, &param.values),
+            ));
+            entries.push((
+                format!("{}{}", name, SCALES_SUFFIX),
+                npy::encode_npy(&scales_shape, &param.scales),
+            ));
+        }
+        npy::write_file(path, &npy::encode_npz(&entries)?)
+    }
+
+    /// Loads quantized parameters from a `.npz` file.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut values = BTreeMap::new();
+        let mut scales = BTreeMap::new();
+        for (name, data) in npy::decode_npz(&npy::read_file(path)?)? {
+            let (shape, array) = npy::decode_npy(&data, None)?;
+            if name.ends_with(VALUES_SUFFIX) {
+                if array.iter().any(|&x| x.round() != x || x.abs() > MAX_LEVEL) {
+                    return Err(invalid_data(format!("invalid quantized values: {}", name)));
+                }
+                let array = array.iter().map(|&x| x as i8).collect::<Vec<_>>();
+                let name = &name[..name.len() - VALUES_SUFFIX.len()];
+                values.insert(name.to_string(), (shape, array));
+            } else if name.ends_with(SCALES_SUFFIX) {
+                let name = &name[..name.len() - SCALES_SUFFIX.len()];
+                scales.insert(name.to_string(), array);
+            } else {
+                return Err(invalid_data(format!(
+                    "unknown entry in quantized npz: {}",
+                    name
+                )));
+            }
+        }
+        let mut params = BTreeMap::new();
+        for (name, (shape, array)) in values {
+            let param = scales
+                .remove(&name)
+                .and_then(|scales| QuantizedTensor::from_parts(shape, array, scales))
+                .ok_or_else(|| invalid_data(format!("invalid quantized parameter: {}", name)))?;
+            let names = name.split('/').map(|s| s.to_string()).collect();
+            params.insert(names, param);
+        }
+        if let Some(name) = scales.keys().next() {
+            return Err(invalid_data(format!("missing quantized values: {}", name)));
+        }
+        Ok(QuantizedModel { params })
+    }
+
+    /// Initializes all parameters in the model with the dequantized values on the default
+    /// device.
+    pub fn dequantize_into<M: Model>(&self, model: &mut M) -> io::Result<()> {
+        self.dequantize_into_on::<M, AnyDevice>(model, None)
+    }
+
+    /// Initializes all parameters in the model with the dequantized values on the specified
+    /// device.
+    ///
+    /// The quantized model must have exactly one entry for each parameter in the model.
+    pub fn dequantize_into_on<M: Model, D: Device>(
+        &self,
+        model: &mut M,
+        device: Option<&mut D>,
+    ) -> io::Result<()> {
+        model.register_parameters();
+        let mut params = model.get_all_parameters();
+        let mut values = vec![];
+        for (names, quantized) in &self.params {
+            let param = params.remove(names).ok_or_else(|| {
+                invalid_data(format!(
+                    "unknown parameter in quantized model: {}",
+                    names.join("/")
+                ))
+            })?;
+            values.push((param, quantized));
+        }
+        if let Some(names) = params.keys().next() {
+            return Err(invalid_data(format!(
+                "missing parameter in quantized model: {}",
+                names.join("/")
+            )));
+        }
+        let mut device = device;
+        for (mut param, quantized) in values {
+            param.init_by_values_on(
+                quantized.shape,
+                &quantized.dequantize(),
+                device.as_mut().map(|d| &mut **d),
+            );
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,14 @@
-use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use device;
+use functions::tensor_funcs;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
+use shape::MAX_DEPTH;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::marker::PhantomData;
+use Parameter;
+use Shape;
+use Tensor;
 
 macro_rules! impl_serde {
     ($name:ident) => {
@@ -42,8 +49,6 @@ macro_rules! impl_deserialize {
 }
 
 impl_serde!(Node);
-impl_serde!(Parameter);
-impl_serde!(Tensor);
 
 struct DefaultVisitor<T>(PhantomData<T>);
 
@@ -79,3 +84,326 @@ impl<'de, T: Default> Visitor<'de> for DefaultVisitor<T> {
         Ok(T::default())
     }
 }
+
+impl Serialize for Shape {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("Shape", 2)?;
+        state.serialize_field("dims", &self.dims())?;
+        state.serialize_field("batch", &self.batch())?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Shape {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct("Shape", SHAPE_FIELDS, ShapeVisitor)
+    }
+}
+
+const SHAPE_FIELDS: &[&str] = &["dims", "batch"];
+
+struct ShapeVisitor;
+
+impl<'de> Visitor<'de> for ShapeVisitor {
+    type Value = Shape;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("struct Shape")
+    }
+
+    fn visit_seq<V>(self, mut seq: V) -> Result<Shape, V::Error>
+    where
+        V: SeqAccess<'de>,
+    {
+        let dims: Vec<u32> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let batch: u32 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        to_shape(&dims, batch)
+    }
+
+    fn visit_map<V>(self, mut map: V) -> Result<Shape, V::Error>
+    where
+        V: MapAccess<'de>,
+    {
+        let mut dims: Option<Vec<u32>> = None;
+        let mut batch: Option<u32> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "dims" => {
+                    if dims.is_some() {
+                        return Err(de::Error::duplicate_field("dims"));
+                    }
+                    dims = Some(map.next_value()?);
+                }
+                "batch" => {
+                    if batch.is_some() {
+                        return Err(de::Error::duplicate_field("batch"));
+                    }
+                    batch = Some(map.next_value()?);
+                }
+                _ => return Err(de::Error::unknown_field(&key, SHAPE_FIELDS)),
+            }
+        }
+        let dims = dims.ok_or_else(|| de::Error::missing_field("dims"))?;
+        let batch = batch.ok_or_else(|| de::Error::missing_field("batch"))?;
+        to_shape(&dims, batch)
+    }
+}
+
+/// Creates a shape, or returns an error instead of panicking in `Shape::from_dims()`.
+fn to_shape<E: de::Error>(dims: &[u32], batch: u32) -> Result<Shape, E> {
+    if dims.len() > MAX_DEPTH || dims.iter().any(|&d| d == 0) || batch == 0 {
+        return Err(E::custom(format!(
+            "invalid shape: dims: {:?}, batch: {}",
+            dims, batch
+        )));
+    }
+    Ok(Shape::from_dims(dims, batch))
+}
+
+/// Returns an error if tensors can not be created on the default device.
+fn check_default_device<E: de::Error>() -> Result<(), E> {
+    if device::has_default() {
+        Ok(())
+    } else {
+        Err(E::custom("default device is not set"))
+    }
+}
+
+/// Implements `Serialize` and `Deserialize` for a plain struct whose fields are all serializable.
+macro_rules! impl_data_serde {
+    ($name:ident, $visitor:ident, { $($field:ident: $type:ty),* }) => {
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                let fields: &[&str] = &[$(stringify!($field)),*];
+                let mut state = serializer.serialize_struct(stringify!($name), fields.len())?;
+                $(state.serialize_field(stringify!($field), &self.$field)?;)*
+                state.end()
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                const FIELDS: &[&str] = &[$(stringify!($field)),*];
+
+                struct $visitor;
+
+                impl<'de> Visitor<'de> for $visitor {
+                    type Value = $name;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        formatter.write_str(concat!("struct ", stringify!($name)))
+                    }
+
+                    fn visit_seq<V>(self, mut seq: V) -> Result<$name, V::Error>
+                    where
+                        V: SeqAccess<'de>,
+                    {
+                        $(
+                            let $field: $type = seq.next_element()?.ok_or_else(|| {
+                                let index = FIELDS.iter().position(|&f| f == stringify!($field));
+                                de::Error::invalid_length(index.unwrap(), &self)
+                            })?;
+                        )*
+                        Ok($name { $($field),* })
+                    }
+
+                    fn visit_map<V>(self, mut map: V) -> Result<$name, V::Error>
+                    where
+                        V: MapAccess<'de>,
+                    {
+                        $(let mut $field: Option<$type> = None;)*
+                        while let Some(key) = map.next_key::<String>()? {
+                            $(
+                                if key == stringify!($field) {
+                                    if $field.is_some() {
+                                        return Err(de::Error::duplicate_field(stringify!($field)));
+                                    }
+                                    $field = Some(map.next_value()?);
+                                    continue;
+                                }
+                            )*
+                            return Err(de::Error::unknown_field(&key, FIELDS));
+                        }
+                        $(
+                            let $field = $field
+                                .ok_or_else(|| de::Error::missing_field(stringify!($field)))?;
+                        )*
+                        Ok($name { $($field),* })
+                    }
+                }
+
+                deserializer.deserialize_struct(stringify!($name), FIELDS, $visitor)
+            }
+        }
+    };
+}
+
+/// Values of a tensor with its shape.
+struct TensorData {
+    dims: Vec<u32>,
+    batch: u32,
+    values: Vec<f32>,
+}
+
+impl_data_serde!(TensorData, TensorDataVisitor, {
+    dims: Vec<u32>,
+    batch: u32,
+    values: Vec<f32>
+});
+
+impl TensorData {
+    fn new(tensor: &Tensor) -> Self {
+        let shape = tensor.shape();
+        TensorData {
+            dims: shape.dims(),
+            batch: shape.batch(),
+            values: tensor.to_vector(),
+        }
+    }
+
+    fn shape<E: de::Error>(&self) -> Result<Shape, E> {
+        let shape = to_shape(&self.dims, self.batch)?;
+        if self.values.len() != shape.size() {
+            return Err(E::custom(format!(
+                "{} values are given for the shape {}",
+                self.values.len(),
+                shape
+            )));
+        }
+        Ok(shape)
+    }
+
+    /// Overwrites the values of a tensor which has the same shape.
+    fn reset<E: de::Error>(&self, tensor: &mut Tensor) -> Result<(), E> {
+        let (shape, expected) = (self.shape()?, tensor.shape());
+        if shape != expected {
+            return Err(E::custom(format!(
+                "shape mismatch: {} is given for {}",
+                shape, expected
+            )));
+        }
+        tensor.reset_by_slice(&self.values);
+        Ok(())
+    }
+}
+
+/// Values of a parameter, its gradient and its statistics, with the trainable flag.
+struct ParameterData {
+    value: TensorData,
+    gradient: Option<TensorData>,
+    stats: BTreeMap<String, TensorData>,
+    trainable: bool,
+}
+
+impl_data_serde!(ParameterData, ParameterDataVisitor, {
+    value: TensorData,
+    gradient: Option<TensorData>,
+    stats: BTreeMap<String, TensorData>,
+    trainable: bool
+});
+
+/// A valid tensor is serialized as its shape and values, and an invalid one is serialized as
+/// `None`.
+///
+/// Deserialized tensors are placed on the default device.
+impl Serialize for Tensor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.valid() {
+            serializer.serialize_some(&TensorData::new(self))
+        } else {
+            serializer.serialize_none()
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Tensor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<TensorData>::deserialize(deserializer)? {
+            Some(data) => {
+                let shape = data.shape()?;
+                check_default_device()?;
+                Ok(tensor_funcs::input(shape, &data.values))
+            }
+            None => Ok(Tensor::new()),
+        }
+    }
+}
+
+/// A valid parameter is serialized as its value, gradient, statistics and trainable flag, and an
+/// invalid one is serialized as `None`.
+///
+/// Deserialized parameters are placed on the default device.
+///
+/// Remark: Handles created by `Parameter::share()` are serialized as separate parameters, so
+/// tied parameters are stored twice and are deserialized as independent parameters. Tie them
+/// again after deserialization.
+impl Serialize for Parameter {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !self.valid() {
+            return serializer.serialize_none();
+        }
+        let data = ParameterData {
+            value: TensorData::new(&self.value()),
+            gradient: Some(TensorData::new(&self.gradient())),
+            stats: self
+                .stats_names()
+                .into_iter()
+                .map(|name| {
+                    let stats = TensorData::new(&self.stats(&name));
+                    (name, stats)
+                })
+                .collect(),
+            trainable: self.is_trainable(),
+        };
+        serializer.serialize_some(&data)
+    }
+}
+
+impl<'de> Deserialize<'de> for Parameter {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = match Option::<ParameterData>::deserialize(deserializer)? {
+            Some(data) => data,
+            None => return Ok(Parameter::new()),
+        };
+        let shape = data.value.shape()?;
+        check_default_device()?;
+        let mut param = Parameter::from_values(shape, &data.value.values);
+        if let Some(gradient) = data.gradient {
+            gradient.reset(&mut param.gradient())?;
+        }
+        for (name, stats) in data.stats {
+            param.add_stats(&name, stats.shape()?);
+            stats.reset(&mut param.stats(&name))?;
+        }
+        param.set_trainable(data.trainable);
+        Ok(param)
+    }
+}
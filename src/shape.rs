@@ -1,293 +1,271 @@
 use primitiv_sys as _primitiv;
-use std::cmp::{Eq, PartialEq};
-use std::ffi::CString;
+use std::cmp::{self, Eq, Ord, Ordering, PartialEq, PartialOrd};
+use std::collections::HashSet;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Index;
 use std::ptr::{self, NonNull};
+use std::slice;
+use std::sync::Mutex;
 use ApiResult;
 use Result;
 use Wrap;
 
+/// Maximum depth of the shape.
+pub(crate) const MAX_DEPTH: usize = 8;
+
+/// Maximum number of distinct dimension names.
+const MAX_NAMES: usize = 4096;
+
+/// Optional names of the dimensions.
+pub(crate) type DimNames = [Option<&'static str>; MAX_DEPTH];
+
+lazy_static! {
+    /// Names of dimensions used so far. Each name is allocated once and kept until the end of the
+    /// program, so that `Shape` can stay `Copy`.
+    static ref INTERNED_NAMES: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+fn intern_name(name: &str) -> &'static str {
+    let mut names = INTERNED_NAMES.lock().unwrap();
+    if let Some(&interned) = names.get(name) {
+        return interned;
+    }
+    assert!(
+        names.len() < MAX_NAMES,
+        "too many distinct dimension names: at most {} names can be used",
+        MAX_NAMES
+    );
+    let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
 /// Data structure to represent the shape of the node.
-#[derive(Debug)]
+///
+/// The shape is a plain value on the Rust side, and it is converted to the C API object only when
+/// it is passed to the core library.
+///
+/// Each dimension may also have a name, which is kept only on the Rust side. Names are
+/// annotations: they are not compared by `==` and are not seen by the core library.
+#[derive(Clone, Copy)]
 pub struct Shape {
-    inner: NonNull<_primitiv::primitivShape_t>,
+    // Dimensions beyond `depth` are always 1.
+    dims: [u32; MAX_DEPTH],
+    depth: u32,
+    batch: u32,
+    names: DimNames,
 }
 
-impl_wrap_owned!(Shape, primitivShape_t);
-impl_drop!(Shape, primitivDeleteShape);
-
 impl Shape {
     /// Creates a new scalar Shape object.
     pub fn new() -> Self {
-        unsafe {
-            let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivCreateShape(&mut shape_ptr));
-            Shape::from_raw(shape_ptr, true)
+        Shape {
+            dims: [1; MAX_DEPTH],
+            depth: 0,
+            batch: 1,
+            names: [None; MAX_DEPTH],
         }
     }
 
     /// Creates a new Shape object.
     pub fn from_dims(dims: &[u32], batch: u32) -> Self {
-        unsafe {
-            let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivCreateShapeWithDims(
-                dims.as_ptr(),
-                dims.len(),
-                batch,
-                &mut shape_ptr,
-            ));
-            Shape::from_raw(shape_ptr, true)
+        assert!(
+            dims.len() <= MAX_DEPTH && dims.iter().all(|&d| d > 0) && batch > 0,
+            "invalid shape: dims: {:?}, batch: {}",
+            dims,
+            batch
+        );
+        let mut shape = Shape::new();
+        shape.dims[..dims.len()].copy_from_slice(dims);
+        shape.depth = dims.len() as u32;
+        shape.batch = batch;
+        shape.adjust_depth();
+        shape
+    }
+
+    /// Removes trailing 1s from the depth.
+    fn adjust_depth(&mut self) {
+        while self.depth > 0 && self.dims[self.depth as usize - 1] == 1 {
+            self.depth -= 1;
         }
     }
 
     /// Returns the size of the i-th dimension.
     pub fn at(&self, i: u32) -> u32 {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivGetShapeDimSize(
-                self.as_ptr(),
-                i,
-                &mut retval as *mut _,
-            ));
-            retval
-        }
+        self[i]
     }
 
     /// Returns the dimension array.
     pub fn dims(&self) -> Vec<u32> {
-        unsafe {
-            let mut size: usize = 0;
-            check_api_status!(_primitiv::primitivGetShapeDims(
-                self.as_ptr(),
-                ptr::null_mut(),
-                &mut size as *mut _,
-            ));
-            let mut retval = vec![0u32; size];
-            check_api_status!(_primitiv::primitivGetShapeDims(
-                self.as_ptr(),
-                retval.as_mut_ptr(),
-                &mut size as *mut _,
-            ));
-            retval
-        }
+        self.dims[..self.depth as usize].to_vec()
+    }
+
+    /// Returns an iterator over the dimensions up to the depth of the shape.
+    pub fn iter(&self) -> slice::Iter<u32> {
+        self.dims[..self.depth as usize].iter()
     }
 
     /// Returns the depth (length of non-1 dimensions) of the shape.
     pub fn depth(&self) -> u32 {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivGetShapeDepth(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval
-        }
+        self.depth
     }
 
     /// Returns the batch size.
     pub fn batch(&self) -> u32 {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivGetShapeBatchSize(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval
-        }
+        self.batch
     }
 
     /// Returns the number of elements in each sample.
     /// This value is equal to the product of all dimensions.
     pub fn volume(&self) -> u32 {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivGetShapeVolume(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval
-        }
+        self.iter().product()
     }
 
     /// Returns the number of elements in 1 to specified dim.
     pub fn lower_volume(&self, dim: u32) -> u32 {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivGetShapeLowerVolume(
-                self.as_ptr(),
-                dim,
-                &mut retval as *mut _,
-            ));
-            retval
-        }
+        self.iter().take(dim as usize).product()
     }
 
     /// Returns the number of elements in all samples of the mini-batch.
     /// This value is equal to batch() * volume().
     pub fn size(&self) -> usize {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivGetShapeSize(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval as usize
-        }
+        self.batch as usize * self.volume() as usize
     }
 
     /// Checks whether the shape has minibatch or not.
     pub fn has_batch(&self) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivHasShapeBatch(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        self.batch > 1
     }
 
     /// Checks whether two batch size is compatible (broadcastable) or not.
     pub fn has_compatible_batch(&self, other: &Shape) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivHasShapeCompatibleBatch(
-                self.as_ptr(),
-                other.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        self.batch == other.batch || self.batch == 1 || other.batch == 1
     }
 
     /// Checks whether the shape is a scalar or not.
     pub fn is_scalar(&self) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivIsShapeScalar(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        self.depth == 0
     }
 
     /// Checks whether the shape is a column vector or not.
     pub fn is_column_vector(&self) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivIsShapeColumnVector(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        self.depth <= 1
     }
 
     /// Checks whether the shape is a vector or a matrix, or not.
     pub fn is_matrix(&self) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivIsShapeMatrix(
-                self.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        self.depth <= 2
     }
 
     /// Checks whether two shapes have completely same dimensions.
     pub fn has_same_dims(&self, other: &Shape) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivHasShapeSameDims(
-                self.as_ptr(),
-                other.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        self.dims == other.dims
     }
 
     /// Checks whether two shapes have same dimensions without an axis. (LOO: leave one out)
     pub fn has_same_loo_dims(&self, other: &Shape, dim: u32) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivHasShapeSameLooDims(
-                self.as_ptr(),
-                other.as_ptr(),
-                dim,
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        (0..MAX_DEPTH as u32)
+            .filter(|&i| i != dim)
+            .all(|i| self[i] == other[i])
     }
 
     /// Creates a new shape which have one different dimension.
     pub fn resize_dim(&self, dim: u32, m: u32) -> Shape {
-        unsafe {
-            let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivResizeShapeDim(
-                self.as_ptr(),
-                dim,
-                m,
-                &mut shape_ptr,
-            ));
-            Shape::from_raw(shape_ptr, true)
-        }
+        let mut shape = *self;
+        shape.update_dim(dim, m);
+        shape
     }
 
     /// Creates a new shape which have specified batch size.
     pub fn resize_batch(&self, batch: u32) -> Shape {
-        unsafe {
-            let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivResizeShapeBatch(
-                self.as_ptr(),
-                batch,
-                &mut shape_ptr,
-            ));
-            Shape::from_raw(shape_ptr, true)
-        }
+        let mut shape = *self;
+        shape.update_batch(batch);
+        shape
     }
 
     /// Directly updates a specified dimension.
     pub fn update_dim(&mut self, dim: u32, m: u32) {
-        unsafe {
-            check_api_status!(_primitiv::primitivUpdateShapeDim(self.as_mut_ptr(), dim, m));
-        }
+        assert!(
+            (dim as usize) < MAX_DEPTH && m > 0,
+            "invalid dimension: dim: {}, size: {}",
+            dim,
+            m
+        );
+        self.dims[dim as usize] = m;
+        self.depth = cmp::max(self.depth, dim + 1);
+        self.adjust_depth();
     }
 
     /// Directly updates the batch size.
     pub fn update_batch(&mut self, batch: u32) {
-        unsafe {
-            check_api_status!(_primitiv::primitivUpdateShapeBatchSize(
-                self.as_mut_ptr(),
-                batch,
-            ));
-        }
+        assert!(batch > 0, "invalid batch size: {}", batch);
+        self.batch = batch;
     }
-}
 
-impl Clone for Shape {
-    #[inline]
-    fn clone(&self) -> Self {
-        unsafe {
-            let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivCloneShape(self.as_ptr(), &mut shape_ptr));
-            Shape::from_raw(shape_ptr, true)
+    /// Returns the same shape with names given to the dimensions from the first one.
+    ///
+    /// Panics if more than 8 names are given or a name is used twice.
+    ///
+    /// Remark: Names are kept until the end of the program so that `Shape` can be copied, and at
+    /// most 4096 distinct names can be used. Do not generate names for each minibatch.
+    pub fn with_names(mut self, names: &[&str]) -> Shape {
+        assert!(
+            names.len() <= MAX_DEPTH,
+            "too many dimension names: {:?}",
+            names
+        );
+        for (i, name) in names.iter().enumerate() {
+            assert!(
+                !names[..i].contains(name),
+                "duplicated dimension name: {}",
+                name
+            );
         }
+        self.names = [None; MAX_DEPTH];
+        for (dst, &name) in self.names.iter_mut().zip(names) {
+            *dst = Some(intern_name(name));
+        }
+        self
+    }
+
+    /// Returns the name of the i-th dimension.
+    pub fn name(&self, i: u32) -> Option<&'static str> {
+        self.names.get(i as usize).and_then(|&name| name)
+    }
+
+    /// Returns the dimension which has the specified name.
+    pub fn dim_by_name(&self, name: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .position(|&n| n == Some(name))
+            .map(|i| i as u32)
     }
 
-    #[inline]
-    fn clone_from(&mut self, source: &Self) {
+    /// Checks whether any dimension of the shape has a name.
+    pub fn has_names(&self) -> bool {
+        self.names.iter().any(Option::is_some)
+    }
+
+    pub(crate) fn dim_names(&self) -> DimNames {
+        self.names
+    }
+
+    pub(crate) fn set_dim_names(&mut self, names: DimNames) {
+        self.names = names;
+    }
+
+    /// Creates the C API object of the shape.
+    pub(crate) fn to_raw(&self) -> RawShape {
         unsafe {
-            check_api_status!(_primitiv::primitivDeleteShape(self.as_mut_ptr()));
             let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
-            check_api_status!(_primitiv::primitivCloneShape(
-                source.as_ptr(),
+            check_api_status!(_primitiv::primitivCreateShapeWithDims(
+                self.dims.as_ptr(),
+                self.depth as usize,
+                self.batch,
                 &mut shape_ptr,
             ));
-            self.inner = NonNull::new(shape_ptr).expect("pointer must not be null");
+            RawShape::from_raw(shape_ptr, true)
         }
     }
 }
@@ -300,36 +278,106 @@ impl Default for Shape {
 
 impl PartialEq for Shape {
     fn eq(&self, other: &Shape) -> bool {
-        unsafe {
-            let mut retval: u32 = 0;
-            check_api_status!(_primitiv::primitivIsShapeEqualTo(
-                self.as_ptr(),
-                other.as_ptr(),
-                &mut retval as *mut _,
-            ));
-            retval == 1
-        }
+        self.dims == other.dims && self.batch == other.batch
     }
 }
 
 impl Eq for Shape {}
 
+impl Hash for Shape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dims.hash(state);
+        self.batch.hash(state);
+    }
+}
+
+impl PartialOrd for Shape {
+    fn partial_cmp(&self, other: &Shape) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Shapes are ordered by their dimensions first, and then by their batch sizes.
+impl Ord for Shape {
+    fn cmp(&self, other: &Shape) -> Ordering {
+        self.dims[..self.depth as usize]
+            .cmp(&other.dims[..other.depth as usize])
+            .then_with(|| self.batch.cmp(&other.batch))
+    }
+}
+
+impl Index<u32> for Shape {
+    type Output = u32;
+
+    /// Returns the size of the i-th dimension, which is 1 for dimensions beyond the depth.
+    fn index(&self, i: u32) -> &u32 {
+        self.dims.get(i as usize).unwrap_or(&1)
+    }
+}
+
+impl<'a> IntoIterator for &'a Shape {
+    type Item = &'a u32;
+    type IntoIter = slice::Iter<'a, u32>;
+
+    fn into_iter(self) -> slice::Iter<'a, u32> {
+        self.iter()
+    }
+}
+
 impl fmt::Display for Shape {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, d) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{}", d)?;
+        }
+        write!(f, "]x{}", self.batch)
+    }
+}
+
+impl fmt::Debug for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut s = f.debug_struct("Shape");
+        s.field("dims", &self.dims()).field("batch", &self.batch);
+        if self.has_names() {
+            s.field("names", &self.names);
+        }
+        s.finish()
+    }
+}
+
+/// Shape object of the C API, which is used only to pass shapes to or from the core library.
+pub(crate) struct RawShape {
+    inner: NonNull<_primitiv::primitivShape_t>,
+}
+
+impl_wrap_owned!(RawShape, primitivShape_t);
+impl_drop!(RawShape, primitivDeleteShape);
+
+impl RawShape {
+    /// Copies the shape to the Rust side.
+    pub(crate) fn to_shape(&self) -> Shape {
         unsafe {
             let mut size: usize = 0;
-            check_api_status!(_primitiv::primitivRepresentShapeAsString(
+            check_api_status!(_primitiv::primitivGetShapeDims(
                 self.as_ptr(),
                 ptr::null_mut(),
                 &mut size as *mut _,
             ));
-            let buffer = CString::new(vec![b'0'; size]).unwrap().into_raw();
-            check_api_status!(_primitiv::primitivRepresentShapeAsString(
+            let mut dims = vec![0u32; size];
+            check_api_status!(_primitiv::primitivGetShapeDims(
                 self.as_ptr(),
-                buffer,
+                dims.as_mut_ptr(),
                 &mut size as *mut _,
             ));
-            f.write_str(CString::from_raw(buffer).to_str().unwrap())
+            let mut batch: u32 = 0;
+            check_api_status!(_primitiv::primitivGetShapeBatchSize(
+                self.as_ptr(),
+                &mut batch as *mut _,
+            ));
+            Shape::from_dims(&dims, batch)
         }
     }
 }
@@ -371,3 +419,59 @@ impl_shape_from_tuple!(5);
 impl_shape_from_tuple!(6);
 impl_shape_from_tuple!(7);
 impl_shape_from_tuple!(8);
+
+impl From<Vec<u32>> for Shape {
+    fn from(dims: Vec<u32>) -> Shape {
+        Shape::from_dims(&dims, 1)
+    }
+}
+
+impl<'a> From<&'a [u32]> for Shape {
+    fn from(dims: &'a [u32]) -> Shape {
+        Shape::from_dims(dims, 1)
+    }
+}
+
+impl Shape {
+    /// Infers the shape of the result of an elementwise operation between two shapes.
+    ///
+    /// As in the core library, the shapes must have the same dimensions, and only the batch size
+    /// is broadcasted when one of them is 1. Returns `None` if the shapes are not compatible.
+    pub fn broadcast(a: &Shape, b: &Shape) -> Option<Shape> {
+        if !a.has_same_dims(b) {
+            return None;
+        }
+        Some(a.resize_batch(broadcast_size(a.batch(), b.batch())?))
+    }
+}
+
+fn broadcast_size(a: u32, b: u32) -> Option<u32> {
+    if a == b || b == 1 {
+        Some(a)
+    } else if a == 1 {
+        Some(b)
+    } else {
+        None
+    }
+}
+
+/// Infers the shape of the concatenation of `shapes` along the dimension `dim`.
+///
+/// Returns `None` if `shapes` is empty, `dim` is not less than the maximum depth, the shapes have
+/// different dimensions other than `dim`, or their batch sizes are not compatible.
+pub fn concat_shape(shapes: &[&Shape], dim: u32) -> Option<Shape> {
+    if dim as usize >= MAX_DEPTH {
+        return None;
+    }
+    let (first, rest) = shapes.split_first()?;
+    let mut size = first[dim];
+    let mut batch = first.batch();
+    for shape in rest {
+        if !first.has_same_loo_dims(shape, dim) {
+            return None;
+        }
+        size = size.checked_add(shape[dim])?;
+        batch = broadcast_size(batch, shape.batch())?;
+    }
+    Some(first.resize_dim(dim, size).resize_batch(batch))
+}
@@ -0,0 +1,152 @@
+use functions::tensor_funcs as F;
+use std::f32;
+use Tensor;
+
+/// Summary statistics of the values in a tensor.
+///
+/// `min`, `max`, `mean` and `std` are calculated over the finite values only, and they are NaN if
+/// there is no finite value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TensorStats {
+    /// Minimum value.
+    pub min: f32,
+    /// Maximum value.
+    pub max: f32,
+    /// Mean of the values.
+    pub mean: f32,
+    /// Population standard deviation of the values.
+    pub std: f32,
+    /// Number of NaN values.
+    pub nan_count: usize,
+    /// Number of infinite values.
+    pub inf_count: usize,
+}
+
+impl TensorStats {
+    /// Calculates the statistics on the host.
+    fn from_values(values: &[f32]) -> Self {
+        let finite: Vec<f32> = values.iter().cloned().filter(|v| v.is_finite()).collect();
+        let nan_count = values.iter().filter(|v| v.is_nan()).count();
+        let inf_count = values.len() - finite.len() - nan_count;
+        if finite.is_empty() {
+            return TensorStats {
+                min: f32::NAN,
+                max: f32::NAN,
+                mean: f32::NAN,
+                std: f32::NAN,
+                nan_count,
+                inf_count,
+            };
+        }
+        let n = finite.len() as f64;
+        let mean = finite.iter().map(|&v| v as f64).sum::<f64>() / n;
+        let var = finite
+            .iter()
+            .map(|&v| (v as f64 - mean) * (v as f64 - mean))
+            .sum::<f64>()
+            / n;
+        TensorStats {
+            min: finite.iter().cloned().fold(f32::INFINITY, f32::min),
+            max: finite.iter().cloned().fold(f32::NEG_INFINITY, f32::max),
+            mean: mean as f32,
+            std: var.sqrt() as f32,
+            nan_count,
+            inf_count,
+        }
+    }
+
+    /// Returns whether the tensor has any NaN or infinite values.
+    pub fn has_non_finite(&self) -> bool {
+        self.nan_count > 0 || self.inf_count > 0
+    }
+}
+
+/// Histogram of the values in a tensor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    /// Edges of the bins. The `i`-th bin covers `[edges[i], edges[i + 1])`, and the last bin also
+    /// includes its upper edge.
+    pub edges: Vec<f32>,
+    /// Number of values in each bin.
+    pub counts: Vec<usize>,
+}
+
+impl Tensor {
+    /// Calculates summary statistics of all values in the tensor, including all minibatches.
+    ///
+    /// The statistics are calculated by tensor functions on the device, and only a few values are
+    /// transferred to the host. If the tensor turns out to have non-finite values, they are
+    /// calculated again on the host to count them.
+    pub fn stats(&self) -> TensorStats {
+        assert!(self.valid(), "stats() of an invalid tensor");
+        let shape = self.shape();
+        let n = shape.size() as f32;
+        let flat = F::flatten(self);
+        let sum = F::batch::sum(F::sum(&flat, 0)).to_float();
+        let min = F::min(&flat, 0)
+            .to_vector()
+            .into_iter()
+            .fold(f32::INFINITY, f32::min);
+        let max = F::max(&flat, 0)
+            .to_vector()
+            .into_iter()
+            .fold(f32::NEG_INFINITY, f32::max);
+        if sum.is_finite() && min.is_finite() && max.is_finite() {
+            let mean = sum / n;
+            let centered = F::subtract_const(&flat, mean);
+            let var = F::batch::sum(F::sum(&centered * &centered, 0)).to_float() / n;
+            if var.is_finite() {
+                return TensorStats {
+                    min,
+                    max,
+                    mean,
+                    std: var.sqrt(),
+                    nan_count: 0,
+                    inf_count: 0,
+                };
+            }
+        }
+        TensorStats::from_values(&self.to_vector())
+    }
+
+    /// Calculates a histogram of the finite values in the tensor with `bins` equal-width bins
+    /// between the minimum and the maximum values.
+    ///
+    /// Remark: The values are counted on the host.
+    pub fn histogram(&self, bins: usize) -> Histogram {
+        assert!(bins > 0, "number of bins must be positive");
+        let values: Vec<f32> = self
+            .to_vector()
+            .into_iter()
+            .filter(|v| v.is_finite())
+            .collect();
+        let (mut lower, mut upper) = values
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lower, upper), &v| {
+                (lower.min(v), upper.max(v))
+            });
+        if values.is_empty() {
+            lower = 0.0;
+            upper = 1.0;
+        } else if lower == upper {
+            lower -= 0.5;
+            upper += 0.5;
+        }
+        let width = (upper - lower) / bins as f32;
+        let edges = (0..bins + 1)
+            .map(|i| {
+                if i == bins {
+                    upper
+                } else {
+                    lower + width * i as f32
+                }
+            })
+            .collect();
+        let mut counts = vec![0; bins];
+        for v in values {
+            let i = ((v - lower) / width) as usize;
+            counts[i.min(bins - 1)] += 1;
+        }
+        Histogram { edges, counts }
+    }
+}
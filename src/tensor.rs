@@ -1,19 +1,51 @@
 use devices::AnyDevice;
+use display;
+use functions::{self, tensor_funcs};
 use primitiv_sys as _primitiv;
+use shape::{DimNames, RawShape, MAX_DEPTH};
+use std::fmt;
+use std::ops::{self, Range};
 use std::ptr::{self, NonNull};
 use ApiResult;
+use Device;
+use Initializer;
 use Result;
 use Shape;
+use Variable;
 use Wrap;
 
 /// Value with any dimensions.
-#[derive(Debug)]
 pub struct Tensor {
     inner: NonNull<_primitiv::primitivTensor_t>,
     owned: bool,
+    dim_names: DimNames,
 }
 
-impl_wrap!(Tensor, primitivTensor_t);
+impl Wrap<_primitiv::primitivTensor_t> for Tensor {
+    #[inline(always)]
+    fn from_raw(ptr: *mut _primitiv::primitivTensor_t, owned: bool) -> Self {
+        Tensor {
+            inner: NonNull::new(ptr).expect("pointer must not be null"),
+            owned,
+            dim_names: [None; MAX_DEPTH],
+        }
+    }
+
+    #[inline(always)]
+    fn as_ptr(&self) -> *const _primitiv::primitivTensor_t {
+        self.inner.as_ptr()
+    }
+
+    #[inline(always)]
+    fn as_mut_ptr(&mut self) -> *mut _primitiv::primitivTensor_t {
+        self.inner.as_ptr()
+    }
+
+    #[inline(always)]
+    fn is_owned(&self) -> bool {
+        self.owned
+    }
+}
 impl_drop!(Tensor, primitivDeleteTensor);
 
 impl Tensor {
@@ -26,6 +58,26 @@ impl Tensor {
         }
     }
 
+    /// Creates a new minibatched Tensor object from samples on the default device.
+    ///
+    /// Each sample holds the values of `shape` in the column-major order, and the samples are
+    /// arranged along the minibatch dimension.
+    ///
+    /// Panics if `samples` is empty, `shape` has a minibatch, or the length of any sample differs
+    /// from `shape.volume()`.
+    pub fn from_batch<TS: AsRef<[f32]>, S: Into<Shape>>(samples: &[TS], shape: S) -> Self {
+        tensor_funcs::input_batch(samples, shape)
+    }
+
+    /// Creates a new minibatched Tensor object from samples on the specified device.
+    pub fn from_batch_on<TS: AsRef<[f32]>, S: Into<Shape>, D: Device>(
+        samples: &[TS],
+        shape: S,
+        dev: Option<&mut D>,
+    ) -> Self {
+        tensor_funcs::input_batch_on(samples, shape, dev)
+    }
+
     /// Check whether the object is valid or not.
     pub fn valid(&self) -> bool {
         unsafe {
@@ -38,7 +90,7 @@ impl Tensor {
         }
     }
 
-    /// Returns shape of the Tensor.
+    /// Returns shape of the Tensor, including the names of the dimensions.
     pub fn shape(&self) -> Shape {
         unsafe {
             let mut shape_ptr: *mut _primitiv::primitivShape_t = ptr::null_mut();
@@ -46,10 +98,29 @@ impl Tensor {
                 self.as_ptr(),
                 &mut shape_ptr,
             ));
-            Shape::from_raw(shape_ptr, true)
+            let mut shape = RawShape::from_raw(shape_ptr, true).to_shape();
+            shape.set_dim_names(self.dim_names);
+            shape
         }
     }
 
+    /// Returns the tensor with names given to the dimensions from the first one.
+    ///
+    /// The names are used by the `*_named` functions. They are passed on to the results of those
+    /// functions and of elementwise functions and operators, which keep the dimensions.
+    pub fn with_dim_names(mut self, names: &[&str]) -> Self {
+        self.dim_names = Shape::new().with_names(names).dim_names();
+        self
+    }
+
+    pub(crate) fn dim_names(&self) -> DimNames {
+        self.dim_names
+    }
+
+    pub(crate) fn set_dim_names(&mut self, names: DimNames) {
+        self.dim_names = names;
+    }
+
     /// Returns the Device object related to the internal memory.
     pub fn device(&self) -> AnyDevice {
         unsafe {
@@ -80,6 +151,10 @@ impl Tensor {
     /// Retrieves internal values in the tensor as a vector.
     /// Remark: Each resulting values a re ordered by the column-major order, and the batch size is
     /// assumed as the last dimension of the tensor.
+    ///
+    /// Remark: The core API does not expose the memory of devices, so the values are always
+    /// copied, even on host devices. Zero-copy access is not provided until the core API supports
+    /// it.
     pub fn to_vector(&self) -> Vec<f32> {
         unsafe {
             // Use a vector as a C-style array because it must be a contiguous array actually.
@@ -100,7 +175,114 @@ impl Tensor {
         }
     }
 
+    /// Retrieves one value in the tensor specified by indices and a batch index.
+    ///
+    /// `indices` are the indices along the leading dimensions, and the omitted trailing ones are
+    /// regarded as 0. Only the specified value is transferred from the device.
+    ///
+    /// Panics if any index is out of range of `shape()`.
+    pub fn get(&self, indices: &[u32], batch: u32) -> f32 {
+        let shape = self.shape();
+        let offset = self.check_index(&shape, indices, batch);
+        let x = tensor_funcs::batch::slice(self, batch, batch + 1);
+        let x = tensor_funcs::reshape(&x, [shape.volume()]);
+        tensor_funcs::slice(&x, 0, offset, offset + 1).to_float()
+    }
+
+    /// Updates one value in the tensor specified by indices and a batch index.
+    ///
+    /// See `get()` for the meaning of the arguments.
+    ///
+    /// Remark: The core library has no API to write a part of a tensor, so all values are
+    /// transferred to the host and back to the device.
+    pub fn set(&mut self, indices: &[u32], batch: u32, value: f32) {
+        let shape = self.shape();
+        let offset = self.check_index(&shape, indices, batch) + batch * shape.volume();
+        let mut values = self.to_vector();
+        values[offset as usize] = value;
+        self.reset_by_slice(&values);
+    }
+
+    /// Returns a new tensor with values in the ranges along each dimension and the minibatch.
+    ///
+    /// `ranges` specify the ranges of the leading dimensions, and the omitted trailing dimensions
+    /// are retained as they are.
+    ///
+    /// Panics if any range is empty or out of range of `shape()`.
+    pub fn get_range(&self, ranges: &[Range<u32>], batch: Range<u32>) -> Tensor {
+        let shape = self.shape();
+        for (dim, range) in ranges.iter().enumerate() {
+            let size = shape.at(dim as u32);
+            assert!(
+                range.start < range.end && range.end <= size,
+                "range {:?} is invalid for dimension {} of shape {}",
+                range,
+                dim,
+                shape
+            );
+        }
+        assert!(
+            batch.start < batch.end && batch.end <= shape.batch(),
+            "range {:?} is invalid for the minibatch of shape {}",
+            batch,
+            shape
+        );
+        let mut x = tensor_funcs::batch::slice(self, batch.start, batch.end);
+        for (dim, range) in ranges.iter().enumerate() {
+            x = tensor_funcs::slice(&x, dim as u32, range.start, range.end);
+        }
+        x
+    }
+
+    /// Validates indices and returns the offset of the value in the sample.
+    fn check_index(&self, shape: &Shape, indices: &[u32], batch: u32) -> u32 {
+        assert!(
+            batch < shape.batch(),
+            "batch index {} is out of range for shape {}",
+            batch,
+            shape
+        );
+        let mut offset = 0;
+        let mut stride = 1;
+        for (dim, &i) in indices.iter().enumerate() {
+            let size = shape.at(dim as u32);
+            assert!(
+                i < size,
+                "index {} is out of range for dimension {} of shape {}",
+                i,
+                dim,
+                shape
+            );
+            offset += i * stride;
+            stride *= size;
+        }
+        offset
+    }
+
+    /// Retrieves internal values in the tensor as a list of samples in the minibatch.
+    ///
+    /// This is the inverse of `Tensor::from_batch()`.
+    pub fn to_batches(&self) -> Vec<Vec<f32>> {
+        functions::split_samples(&self.shape(), self.to_vector())
+    }
+
+    /// Returns whether two tensors have the same shape and element-wise close values.
+    ///
+    /// See `Variable::allclose()` for details.
+    pub fn allclose<T: AsRef<Self>>(&self, other: T, rtol: f32, atol: f32) -> bool {
+        Variable::allclose(self, other, rtol, atol)
+    }
+
+    /// Returns the maximum absolute difference between the values of two tensors.
+    ///
+    /// Panics if the shapes differ.
+    pub fn max_abs_diff<T: AsRef<Self>>(&self, other: T) -> f32 {
+        Variable::max_abs_diff(self, other)
+    }
+
     /// Retrieves argmax indices along an axis.
+    ///
+    /// Remark: The indices are returned on the host, since the core library has no integer tensors.
     pub fn argmax(&self, dim: u32) -> Vec<u32> {
         unsafe {
             let mut size: usize = 0;
@@ -122,6 +304,8 @@ impl Tensor {
     }
 
     /// Retrieves argmin indices along an axis.
+    ///
+    /// Remark: As with `argmax()`, the indices are returned on the host.
     pub fn argmin(&self, dim: u32) -> Vec<u32> {
         unsafe {
             let mut size: usize = 0;
@@ -165,7 +349,7 @@ impl Tensor {
             let mut tensor_ptr: *mut _primitiv::primitivTensor_t = ptr::null_mut();
             check_api_status!(_primitiv::primitivReshapeTensor(
                 self.as_ptr(),
-                new_shape.as_ptr(),
+                new_shape.to_raw().as_ptr(),
                 &mut tensor_ptr,
             ));
             Tensor::from_raw(tensor_ptr, true)
@@ -216,6 +400,74 @@ impl Tensor {
             self
         }
     }
+
+    /// Replaces all values with those of another tensor with the same shape.
+    ///
+    /// Unlike assigning a new Tensor object, this function keeps the identity of the tensor, so it
+    /// can be used to update the values of a parameter (e.g., `Parameter::value()`).
+    ///
+    /// Remark: The core API has no in-place copy between tensors, so this function runs two
+    /// kernels, `reset(0.0)` and `inplace_add()`. It does not allocate any temporary tensor.
+    pub fn reset_by_tensor(&mut self, x: &Tensor) -> &mut Self {
+        self.reset(0.0);
+        self.inplace_add(x)
+    }
+
+    /// Replaces all values using an initializer.
+    pub fn reset_by_initializer<I: Initializer>(&mut self, initializer: &I) -> &mut Self {
+        initializer.apply(self);
+        self
+    }
+
+    /// Directly multiplies a value elementwise.
+    ///
+    /// Remark: The core library has no in-place kernel for this operation, so a temporary tensor
+    /// is created on the device and copied into this tensor.
+    pub fn inplace_multiply(&mut self, x: &Tensor) -> &mut Self {
+        let y = tensor_funcs::multiply(&*self, x);
+        self.reset_by_tensor(&y)
+    }
+
+    /// Directly divides by a value elementwise.
+    ///
+    /// Remark: This function creates a temporary tensor as `inplace_multiply()`.
+    pub fn inplace_divide(&mut self, x: &Tensor) -> &mut Self {
+        let y = tensor_funcs::divide(&*self, x);
+        self.reset_by_tensor(&y)
+    }
+
+    /// Directly adds a scaled value, i.e., `self += a * x`.
+    ///
+    /// Remark: This function creates a temporary tensor for `a * x` on the device.
+    pub fn inplace_axpy(&mut self, a: f32, x: &Tensor) -> &mut Self {
+        let y = tensor_funcs::multiply_const(x, a);
+        self.inplace_add(&y)
+    }
+
+    /// Directly clamps all values into the range `[lower, upper]`.
+    ///
+    /// Values in the range, including NaNs, are kept exactly as they are.
+    ///
+    /// Remark: The core library has no elementwise minimum or maximum, so the values are
+    /// clamped on the host and transferred back to the device.
+    pub fn inplace_clamp(&mut self, lower: f32, upper: f32) -> &mut Self {
+        assert!(
+            lower <= upper,
+            "lower bound {} must not be greater than upper bound {}",
+            lower,
+            upper
+        );
+        let mut values = self.to_vector();
+        for value in &mut values {
+            if *value < lower {
+                *value = lower;
+            } else if *value > upper {
+                *value = upper;
+            }
+        }
+        self.reset_by_slice(&values);
+        self
+    }
 }
 
 impl Clone for Tensor {
@@ -227,7 +479,9 @@ impl Clone for Tensor {
                 self.as_ptr(),
                 &mut tensor_ptr,
             ));
-            Tensor::from_raw(tensor_ptr, true)
+            let mut tensor = Tensor::from_raw(tensor_ptr, true);
+            tensor.dim_names = self.dim_names;
+            tensor
         }
     }
 
@@ -242,6 +496,75 @@ impl Clone for Tensor {
             ));
             self.inner = NonNull::new(tensor_ptr).expect("pointer must not be null");
         }
+        self.dim_names = source.dim_names;
+    }
+}
+
+impl fmt::Debug for Tensor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.valid() {
+            return f.debug_struct("Tensor").field("valid", &false).finish();
+        }
+        let shape = self.shape();
+        let mut s = f.debug_struct("Tensor");
+        s.field("shape", &format_args!("{}", shape));
+        let stats = self.stats();
+        s.field("min", &stats.min)
+            .field("max", &stats.max)
+            .field("mean", &stats.mean)
+            .finish()
+    }
+}
+
+impl fmt::Display for Tensor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.valid() {
+            return f.write_str("Tensor(invalid)");
+        }
+        let shape = self.shape();
+        writeln!(
+            f,
+            "Tensor(shape={}, device={:?})",
+            shape,
+            self.device().kind()
+        )?;
+        display::write_values(f, &shape, &self.to_vector())
+    }
+}
+
+impl ops::AddAssign for Tensor {
+    fn add_assign(&mut self, rhs: Tensor) {
+        self.inplace_add(&rhs);
+    }
+}
+
+impl<'a> ops::AddAssign<&'a Tensor> for Tensor {
+    fn add_assign(&mut self, rhs: &'a Tensor) {
+        self.inplace_add(rhs);
+    }
+}
+
+impl ops::SubAssign for Tensor {
+    fn sub_assign(&mut self, rhs: Tensor) {
+        self.inplace_subtract(&rhs);
+    }
+}
+
+impl<'a> ops::SubAssign<&'a Tensor> for Tensor {
+    fn sub_assign(&mut self, rhs: &'a Tensor) {
+        self.inplace_subtract(rhs);
+    }
+}
+
+impl ops::MulAssign<f32> for Tensor {
+    fn mul_assign(&mut self, rhs: f32) {
+        self.inplace_multiply_const(rhs);
+    }
+}
+
+impl ops::DivAssign<f32> for Tensor {
+    fn div_assign(&mut self, rhs: f32) {
+        self.inplace_multiply_const(1.0 / rhs);
     }
 }
 
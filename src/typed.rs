@@ -0,0 +1,269 @@
+//! Nodes with dimensions checked at compile time.
+//!
+//! Each dimension is a marker type implementing `Dim`, and the shape of a `TypedNode` is a tuple
+//! of them, e.g. `TypedNode<(Hidden, Input)>` for a `[hidden, input]` matrix. The functions in
+//! this module only accept arguments with compatible shapes, so mismatches are reported by the
+//! compiler. The minibatch size is not a part of the type and is checked at runtime by the core
+//! library as usual.
+//!
+//! A `TypedNode` is a thin wrapper of `Node`: use `node()` or `into_node()` to fall back to the
+//! dynamic functions, and `TypedNode::new()` to come back.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! typed_dim!(Hidden = 8);
+//! typed_dim!(Input = 2);
+//!
+//! let w = TypedNode::<(Hidden, Input)>::new(F::parameter(&mut pw));
+//! let x = TypedNode::<(Input,)>::new(F::input(([2], 4), &data));
+//! let h = typed::matmul(&w, &x); // TypedNode<(Hidden,)>
+//! ```
+
+use functions::node_funcs as F;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops;
+use Node;
+use Shape;
+
+/// Dimension whose size is known at compile time.
+///
+/// Use the `typed_dim!` macro to define a new dimension.
+pub trait Dim {
+    /// Size of the dimension.
+    const SIZE: u32;
+}
+
+/// Dimension whose size is the sum of two dimensions, which is made by concatenation.
+pub struct Sum<A, B>(PhantomData<(A, B)>);
+
+impl<A: Dim, B: Dim> Dim for Sum<A, B> {
+    const SIZE: u32 = A::SIZE + B::SIZE;
+}
+
+/// Shape whose dimensions are known at compile time.
+///
+/// This is implemented for tuples of up to 4 `Dim`s, and `()` for scalars.
+pub trait TypedShape {
+    /// Returns the dimensions.
+    fn dims() -> Vec<u32>;
+
+    /// Returns the dimensions as a `Shape` without minibatch.
+    fn shape() -> Shape {
+        Shape::from_dims(&Self::dims(), 1)
+    }
+}
+
+impl TypedShape for () {
+    fn dims() -> Vec<u32> {
+        vec![]
+    }
+}
+
+impl<A: Dim> TypedShape for (A,) {
+    fn dims() -> Vec<u32> {
+        vec![A::SIZE]
+    }
+}
+
+impl<A: Dim, B: Dim> TypedShape for (A, B) {
+    fn dims() -> Vec<u32> {
+        vec![A::SIZE, B::SIZE]
+    }
+}
+
+impl<A: Dim, B: Dim, C: Dim> TypedShape for (A, B, C) {
+    fn dims() -> Vec<u32> {
+        vec![A::SIZE, B::SIZE, C::SIZE]
+    }
+}
+
+impl<A: Dim, B: Dim, C: Dim, D: Dim> TypedShape for (A, B, C, D) {
+    fn dims() -> Vec<u32> {
+        vec![A::SIZE, B::SIZE, C::SIZE, D::SIZE]
+    }
+}
+
+/// Node whose dimensions are given by the type `S`.
+pub struct TypedNode<S: TypedShape> {
+    node: Node,
+    shape: PhantomData<S>,
+}
+
+impl<S: TypedShape> TypedNode<S> {
+    /// Wraps a node.
+    ///
+    /// Panics if the dimensions of the node differ from `S`.
+    pub fn new(node: Node) -> Self {
+        let shape = node.shape();
+        let expected = S::shape();
+        assert!(
+            shape.has_same_dims(&expected),
+            "shape mismatch: the node has {}, but the type requires {}",
+            shape,
+            expected
+        );
+        TypedNode::from_node_unchecked(node)
+    }
+
+    fn from_node_unchecked(node: Node) -> Self {
+        TypedNode {
+            node,
+            shape: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped node.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Unwraps the node.
+    pub fn into_node(self) -> Node {
+        self.node
+    }
+}
+
+impl<S: TypedShape> Clone for TypedNode<S> {
+    fn clone(&self) -> Self {
+        TypedNode::from_node_unchecked(self.node.clone())
+    }
+}
+
+impl<S: TypedShape> fmt::Debug for TypedNode<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("TypedNode").field(&self.node).finish()
+    }
+}
+
+impl<S: TypedShape> AsRef<Node> for TypedNode<S> {
+    fn as_ref(&self) -> &Node {
+        &self.node
+    }
+}
+
+impl<S: TypedShape> From<TypedNode<S>> for Node {
+    fn from(x: TypedNode<S>) -> Node {
+        x.node
+    }
+}
+
+/// Matrix multiplication of `[M, K]` and `[K, N]`, or `[M, K]` and `[K]`.
+pub trait MatMul<Rhs: TypedShape>: TypedShape {
+    /// Shape of the result.
+    type Output: TypedShape;
+}
+
+impl<M: Dim, K: Dim, N: Dim> MatMul<(K, N)> for (M, K) {
+    type Output = (M, N);
+}
+
+impl<M: Dim, K: Dim> MatMul<(K,)> for (M, K) {
+    type Output = (M,);
+}
+
+pub fn matmul<S1, S2>(a: &TypedNode<S1>, b: &TypedNode<S2>) -> TypedNode<S1::Output>
+where
+    S1: MatMul<S2>,
+    S2: TypedShape,
+{
+    TypedNode::from_node_unchecked(F::matmul(&a.node, &b.node))
+}
+
+pub fn add<S: TypedShape>(a: &TypedNode<S>, b: &TypedNode<S>) -> TypedNode<S> {
+    TypedNode::from_node_unchecked(F::add(&a.node, &b.node))
+}
+
+pub fn subtract<S: TypedShape>(a: &TypedNode<S>, b: &TypedNode<S>) -> TypedNode<S> {
+    TypedNode::from_node_unchecked(F::subtract(&a.node, &b.node))
+}
+
+pub fn multiply<S: TypedShape>(a: &TypedNode<S>, b: &TypedNode<S>) -> TypedNode<S> {
+    TypedNode::from_node_unchecked(F::multiply(&a.node, &b.node))
+}
+
+pub fn transpose<A: Dim, B: Dim>(x: &TypedNode<(A, B)>) -> TypedNode<(B, A)> {
+    TypedNode::from_node_unchecked(F::transpose(&x.node))
+}
+
+/// Concatenates two matrices along the first dimension.
+pub fn concat_rows<A: Dim, B: Dim, C: Dim>(
+    a: &TypedNode<(A, C)>,
+    b: &TypedNode<(B, C)>,
+) -> TypedNode<(Sum<A, B>, C)> {
+    TypedNode::from_node_unchecked(F::concat(&[&a.node, &b.node], 0))
+}
+
+/// Concatenates two matrices along the second dimension.
+pub fn concat_cols<A: Dim, B: Dim, C: Dim>(
+    a: &TypedNode<(A, B)>,
+    b: &TypedNode<(A, C)>,
+) -> TypedNode<(A, Sum<B, C>)> {
+    TypedNode::from_node_unchecked(F::concat(&[&a.node, &b.node], 1))
+}
+
+/// Changes the dimensions of a node.
+///
+/// Remark: The volumes of `S1` and `S2` can not be compared at compile time without const
+/// generics, so they are checked when this function is called, and it panics if they differ.
+pub fn reshape<S1: TypedShape, S2: TypedShape>(x: &TypedNode<S1>) -> TypedNode<S2> {
+    let (from, to) = (S1::shape(), S2::shape());
+    assert!(
+        from.volume() == to.volume(),
+        "can not reshape {} into {}",
+        from,
+        to
+    );
+    TypedNode::from_node_unchecked(F::reshape(&x.node, to.resize_batch(x.node.shape().batch())))
+}
+
+impl<'a, 'b, S: TypedShape> ops::Add<&'b TypedNode<S>> for &'a TypedNode<S> {
+    type Output = TypedNode<S>;
+
+    fn add(self, rhs: &'b TypedNode<S>) -> TypedNode<S> {
+        add(self, rhs)
+    }
+}
+
+impl<'a, 'b, S: TypedShape> ops::Sub<&'b TypedNode<S>> for &'a TypedNode<S> {
+    type Output = TypedNode<S>;
+
+    fn sub(self, rhs: &'b TypedNode<S>) -> TypedNode<S> {
+        subtract(self, rhs)
+    }
+}
+
+impl<'a, 'b, S: TypedShape> ops::Mul<&'b TypedNode<S>> for &'a TypedNode<S> {
+    type Output = TypedNode<S>;
+
+    fn mul(self, rhs: &'b TypedNode<S>) -> TypedNode<S> {
+        multiply(self, rhs)
+    }
+}
+
+/// Defines a marker type of a dimension.
+///
+/// # Examples
+///
+/// ```ignore
+/// typed_dim!(Hidden = 128);
+/// typed_dim!(pub Vocab = 10000);
+/// ```
+#[macro_export]
+macro_rules! typed_dim {
+    (pub $name:ident = $size:expr) => {
+        pub struct $name;
+
+        impl $crate::typed::Dim for $name {
+            const SIZE: u32 = $size;
+        }
+    };
+    ($name:ident = $size:expr) => {
+        struct $name;
+
+        impl $crate::typed::Dim for $name {
+            const SIZE: u32 = $size;
+        }
+    };
+}
@@ -0,0 +1,93 @@
+#[macro_use]
+extern crate primitiv;
+
+use primitiv::constraints as C;
+use primitiv::devices as D;
+use primitiv::optimizers as O;
+use primitiv::tensor_functions as F;
+use primitiv::Constraint;
+use primitiv::Model;
+use primitiv::Optimizer;
+use primitiv::Parameter;
+
+#[derive(Model)]
+struct Embedding {
+    pw: Parameter,
+}
+
+#[derive(Model)]
+struct Classifier {
+    #[primitiv(submodel)]
+    embed: Embedding,
+    pw: Parameter,
+}
+
+#[test]
+fn builtin_constraints() {
+    let mut dev = D::Naive::new();
+    let values = [3.0, -4.0, 0.0, 0.5];
+    {
+        let mut x = F::input_on([2, 2], &values, Some(&mut dev));
+        C::MaxNorm::new(1.0, 0).apply(&mut x);
+        assert_tensor_close!(
+            x,
+            F::input_on([2, 2], &[0.6, -0.8, 0.0, 0.5], Some(&mut dev))
+        );
+    }
+    {
+        let mut x = F::input_on([2, 2], &values, Some(&mut dev));
+        C::NonNegative::new().apply(&mut x);
+        assert_eq!(x.to_vector(), vec![3.0, 0.0, 0.0, 0.5]);
+    }
+    {
+        let mut x = F::input_on([2, 2], &values, Some(&mut dev));
+        C::ClipByValue::new(-1.0, 1.0).apply(&mut x);
+        assert_eq!(x.to_vector(), vec![1.0, -1.0, 0.0, 0.5]);
+    }
+    {
+        let mut x = F::input_on([2, 2], &values, Some(&mut dev));
+        C::UnitNorm::new(1).apply(&mut x);
+        assert_tensor_close!(
+            x,
+            F::input_on([2, 2], &[1.0, -0.992278, 0.0, 0.124035], Some(&mut dev))
+        );
+    }
+}
+
+#[test]
+fn constraints_are_applied_after_update() {
+    let mut dev = D::Naive::new();
+    let mut model = Classifier {
+        embed: Embedding {
+            pw: Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev)),
+        },
+        pw: Parameter::from_values_on([2], &[3.0, 4.0], Some(&mut dev)),
+    };
+    let mut optimizer = O::SGD::new(1.0);
+    optimizer.add_model(&mut model);
+    model.constrain(&["embed"], C::NonNegative::new());
+    model.pw.add_constraint(C::ClipByValue::new(0.0, 2.5));
+
+    model.embed.pw.gradient().reset(1.5);
+    model.pw.gradient().reset(1.0);
+    optimizer.update();
+    assert_eq!(model.embed.pw.value().to_vector(), vec![0.0, 0.5]);
+    assert_eq!(model.pw.value().to_vector(), vec![2.0, 2.5]);
+
+    model.pw.clear_constraints();
+    model.pw.gradient().reset(-1.0);
+    optimizer.update();
+    assert_eq!(model.pw.value().to_vector(), vec![3.0, 3.5]);
+}
+
+#[test]
+#[should_panic(expected = "no parameter found at decoder")]
+fn constrain_unknown_path() {
+    let mut model = Classifier {
+        embed: Embedding {
+            pw: Parameter::new(),
+        },
+        pw: Parameter::new(),
+    };
+    model.constrain(&["decoder"], C::UnitNorm::new(0));
+}
@@ -0,0 +1,193 @@
+#[macro_use]
+extern crate primitiv;
+
+use primitiv::devices as D;
+use primitiv::node_functions as F;
+use primitiv::Graph;
+use primitiv::Node;
+
+fn make_node(dev: &mut D::Naive, g: &mut Graph) -> Node {
+    F::input_into([2], &[1.0, 2.0], Some(dev), Some(g))
+}
+
+#[test]
+fn node_is_valid_before_clear() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    assert!(x.valid());
+    assert_eq!(x.to_vector(), vec![1.0, 2.0]);
+}
+
+#[test]
+fn debug_shows_summary() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = F::input_into([4], &[1.0, 2.0, 3.0, 6.0], Some(&mut dev), Some(&mut g));
+    assert_eq!(
+        format!("{:?}", x),
+        "Node { shape: [4]x1, min: 1.0, max: 6.0, mean: 3.0 }"
+    );
+    g.clear();
+    assert_eq!(format!("{:?}", x), "Node { valid: false }");
+}
+
+#[test]
+fn node_is_invalidated_by_clear() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    let y = x.clone();
+    g.clear();
+    assert!(!x.valid());
+    assert!(!y.valid());
+    let z = make_node(&mut dev, &mut g);
+    assert!(z.valid());
+}
+
+#[test]
+fn node_is_invalidated_by_graph_drop() {
+    let mut dev = D::Naive::new();
+    let x = {
+        let mut g = Graph::new();
+        make_node(&mut dev, &mut g)
+    };
+    assert!(!x.valid());
+}
+
+#[test]
+#[should_panic(expected = "the node is stale")]
+fn to_vector_after_clear_panics() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    g.clear();
+    x.to_vector();
+}
+
+#[test]
+#[should_panic(expected = "the node is stale")]
+fn shape_after_clear_panics() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    g.clear();
+    x.shape();
+}
+
+#[test]
+#[should_panic(expected = "the node is stale")]
+fn backward_after_graph_drop_panics() {
+    let mut dev = D::Naive::new();
+    let x = {
+        let mut g = Graph::new();
+        F::sum(make_node(&mut dev, &mut g), 0)
+    };
+    x.backward();
+}
+
+#[test]
+#[should_panic(expected = "the node is stale")]
+fn function_with_stale_node_panics() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    g.clear();
+    F::tanh(&x);
+}
+
+#[test]
+fn forward_all_returns_values_in_order() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    let y = F::sum(&x, 0);
+    let values = g.forward_all(&[&y, &x]);
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0].to_vector(), vec![3.0]);
+    assert_eq!(values[1].to_vector(), vec![1.0, 2.0]);
+}
+
+#[test]
+fn read_into_writes_to_buffer() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    let mut buffer = [0.0; 2];
+    x.read_into(&mut buffer);
+    assert_eq!(buffer, [1.0, 2.0]);
+}
+
+#[test]
+#[should_panic(expected = "buffer length mismatch")]
+fn read_into_with_wrong_length_panics() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    let mut buffer = [0.0; 3];
+    x.read_into(&mut buffer);
+}
+
+#[test]
+fn assert_node_close_compares_values() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g);
+    let y = F::input_into([2], &[1.0, 2.0], Some(&mut dev), Some(&mut g));
+    assert_node_close!(x, y);
+    assert!(!x.allclose(&F::tanh(&y), 0.1, 0.1));
+}
+
+#[test]
+fn named_functions_keep_names() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = F::input_into(
+        [2, 3],
+        &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        Some(&mut dev),
+        Some(&mut g),
+    )
+    .with_dim_names(&["hidden", "time"]);
+    assert_shape!(x, [2, 3]);
+
+    let y = F::sum_named(&x, "time");
+    assert_shape!(y, [2], 1);
+    assert_eq!(y.shape().name(1), Some("time"));
+    assert_eq!(y.to_vector(), vec![9.0, 12.0]);
+
+    let z = F::pick_named(&x, &[1], "hidden");
+    assert_eq!(z.to_vector(), vec![2.0, 4.0, 6.0]);
+    let zs = F::split_named(&x, "time", 3);
+    assert_eq!(zs[2].to_vector(), vec![5.0, 6.0]);
+    assert_eq!(zs[2].shape().dim_by_name("hidden"), Some(0));
+    let w = F::concat_named(&zs, "time");
+    assert_shape!(w, [2, 3]);
+    assert_eq!(w.shape().name(1), Some("time"));
+
+    assert!(!F::sum(&x, 1).shape().has_names());
+
+    let y = F::sum_named(F::tanh(&x) * 2.0 + &x, "time");
+    assert_shape!(y, [2], 1);
+    assert_eq!(y.shape().name(0), Some("hidden"));
+    let y = F::add(F::exp(&x), -x.clone());
+    assert_eq!(y.shape().dim_by_name("time"), Some(1));
+}
+
+#[test]
+#[should_panic(expected = "no dimension named `batch`")]
+fn named_function_with_unknown_name() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g).with_dim_names(&["hidden"]);
+    F::sum_named(&x, "batch");
+}
+
+#[test]
+#[should_panic(expected = "shape of `x` is [2]x1 (dimension names: [hidden,_]), expected [1,2]x1")]
+fn assert_shape_failure() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = make_node(&mut dev, &mut g).with_dim_names(&["hidden"]);
+    assert_shape!(x, [1, 2]);
+}
@@ -427,3 +427,17 @@ fn derive_with_model_test() {
     assert!(m.find_parameter(&["model4", "Variant9.pw1"]).is_some());
     assert!(m.find_parameter(&["model4", "Variant9.pw2"]).is_some());
 }
+
+#[test]
+fn get_all_parameters_test() {
+    let mut m = Model6::new();
+    m.register_parameters();
+    let params = m.get_all_parameters();
+    assert_eq!(params.len(), 113);
+    assert!(params.contains_key(&vec!["model1".to_string(), "pw3.0".to_string()]));
+    assert!(params.contains_key(&vec!["model4".to_string(), "Variant9.pw2".to_string()]));
+    for names in params.keys() {
+        let names = names.iter().map(|s| &s[..]).collect::<Vec<_>>();
+        assert!(m.find_parameter(&names).is_some());
+    }
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "ndarray")]
+
+extern crate ndarray;
+extern crate primitiv;
+
+use ndarray::{arr2, arr3, ArrayD};
+use primitiv::devices as D;
+use primitiv::node_functions as F;
+use primitiv::Graph;
+use primitiv::Tensor;
+
+#[test]
+fn tensor_from_ndarray_uses_column_major_layout() {
+    let mut dev = D::Naive::new();
+    let array = arr2(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]).into_dyn();
+    let x = Tensor::from_ndarray_on(array.clone(), None, Some(&mut dev));
+    assert_eq!(x.shape().dims(), vec![2, 3]);
+    assert_eq!(x.shape().batch(), 1);
+    assert_eq!(x.to_vector(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    assert_eq!(x.to_ndarray(), array);
+}
+
+#[test]
+fn tensor_from_ndarray_with_batch_axis() {
+    let mut dev = D::Naive::new();
+    // Two samples of 2x2 matrices along the first axis.
+    let array = arr3(&[[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]).into_dyn();
+    let x = Tensor::from_ndarray_on(array.clone(), Some(0), Some(&mut dev));
+    assert_eq!(x.shape().dims(), vec![2, 2]);
+    assert_eq!(x.shape().batch(), 2);
+    assert_eq!(x.to_vector(), vec![1.0, 3.0, 2.0, 4.0, 5.0, 7.0, 6.0, 8.0]);
+    let y: ArrayD<f32> = x.to_ndarray();
+    assert_eq!(y.shape(), &[2, 2, 2]);
+    assert_eq!(y.permuted_axes(vec![2, 0, 1]), array);
+}
+
+#[test]
+fn input_ndarray_creates_node() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let array = arr2(&[[1.0, 2.0], [3.0, 4.0]]).into_dyn();
+    let x = F::input_ndarray_into(array.clone(), None, Some(&mut dev), Some(&mut g));
+    assert_eq!(x.to_vector(), vec![1.0, 3.0, 2.0, 4.0]);
+    assert_eq!(x.to_ndarray(), array);
+}
@@ -0,0 +1,179 @@
+#[macro_use]
+extern crate primitiv;
+
+use primitiv::devices as D;
+use primitiv::tensor_functions as F;
+use primitiv::Model;
+use primitiv::Parameter;
+use primitiv::Tensor;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("primitiv-npy-test-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn tensor_npy_roundtrip() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on(
+        ([2, 3], 2),
+        &(0..12).map(|i| i as f32).collect::<Vec<_>>(),
+        Some(&mut dev),
+    );
+    let path = temp_path("roundtrip.npy");
+    x.save_npy(&path).unwrap();
+    let y = Tensor::load_npy_on(&path, Some(2), Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(y.shape().dims(), vec![2, 3]);
+    assert_eq!(y.shape().batch(), 2);
+    assert_eq!(y.to_vector(), x.to_vector());
+}
+
+/// Returns the beginning of a version 1.0 npy file with the header.
+fn npy_header(header: &str) -> Vec<u8> {
+    let mut header = header.to_string();
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&[header.len() as u8, 0]);
+    bytes.extend_from_slice(header.as_bytes());
+    bytes
+}
+
+#[test]
+fn load_c_order_npy() {
+    // numpy.array([[1, 2, 3], [4, 5, 6]], dtype='<f4') in the C order.
+    let mut bytes = npy_header("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }");
+    for i in 1..7 {
+        let bits = (i as f32).to_bits();
+        bytes.extend_from_slice(&[
+            bits as u8,
+            (bits >> 8) as u8,
+            (bits >> 16) as u8,
+            (bits >> 24) as u8,
+        ]);
+    }
+    let path = temp_path("c_order.npy");
+    File::create(&path).unwrap().write_all(&bytes).unwrap();
+
+    let mut dev = D::Naive::new();
+    let x = Tensor::load_npy_on(&path, None, Some(&mut dev)).unwrap();
+    let y = Tensor::load_npy_on(&path, Some(0), Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(x.shape().dims(), vec![2, 3]);
+    assert_eq!(x.to_vector(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    assert_eq!(y.shape().dims(), vec![3]);
+    assert_eq!(y.shape().batch(), 2);
+    assert_eq!(y.to_vector(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+}
+
+#[test]
+fn load_npy_with_huge_shape_fails() {
+    let mut dev = D::Naive::new();
+    for shape in &["(4294967296,)", "(4294967295, 4294967295, 4294967295)"] {
+        let header = format!(
+            "{{'descr': '<f4', 'fortran_order': True, 'shape': {}, }}",
+            shape
+        );
+        let mut bytes = npy_header(&header);
+        bytes.extend_from_slice(&[0; 16]);
+        let path = temp_path("huge.npy");
+        File::create(&path).unwrap().write_all(&bytes).unwrap();
+        let err = Tensor::load_npy_on(&path, None, Some(&mut dev)).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[derive(Model)]
+struct Affine {
+    pw: Parameter,
+    pb: Parameter,
+}
+
+#[derive(Model)]
+struct Network {
+    #[primitiv(submodel)]
+    layers: Vec<Affine>,
+}
+
+impl Network {
+    fn new<D: primitiv::Device>(dev: &mut D, offset: f32) -> Self {
+        let mut network = Network {
+            layers: (0..2)
+                .map(|i| {
+                    let k = offset + i as f32;
+                    Affine {
+                        pw: Parameter::from_values_on(
+                            [2, 2],
+                            &[k, k + 1.0, k + 2.0, k + 3.0],
+                            Some(dev),
+                        ),
+                        pb: Parameter::from_values_on([2], &[k, -k], Some(dev)),
+                    }
+                })
+                .collect(),
+        };
+        network.register_parameters();
+        network
+    }
+}
+
+#[test]
+fn model_npz_roundtrip() {
+    let mut dev = D::Naive::new();
+    let src = Network::new(&mut dev, 1.0);
+    let mut dst = Network::new(&mut dev, 10.0);
+    let path = temp_path("model.npz");
+    src.save_npz(&path).unwrap();
+    dst.load_npz_on(&path, Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    let params = src.get_all_parameters();
+    assert_eq!(params.len(), 4);
+    for (names, param) in params {
+        let names = names.iter().map(|s| &s[..]).collect::<Vec<_>>();
+        let loaded = dst.find_parameter(&names).unwrap();
+        assert_eq!(loaded.shape(), param.shape());
+        assert_eq!(loaded.value().to_vector(), param.value().to_vector());
+    }
+}
+
+#[test]
+fn load_npz_with_missing_parameter_fails() {
+    let mut dev = D::Naive::new();
+    let mut small = Affine {
+        pw: Parameter::from_values_on([1], &[1.0], Some(&mut dev)),
+        pb: Parameter::from_values_on([1], &[2.0], Some(&mut dev)),
+    };
+    small.register_parameters();
+    let mut large = Network::new(&mut dev, 0.0);
+    let path = temp_path("missing.npz");
+    small.save_npz(&path).unwrap();
+    assert!(large.load_npz(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn load_npz_with_wrong_shape_fails() {
+    let mut dev = D::Naive::new();
+    let mut src = Affine {
+        pw: Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev)),
+        pb: Parameter::from_values_on([1], &[3.0], Some(&mut dev)),
+    };
+    src.register_parameters();
+    let mut dst = Affine {
+        pw: Parameter::from_values_on([1, 2], &[0.0, 0.0], Some(&mut dev)),
+        pb: Parameter::from_values_on([1], &[0.0], Some(&mut dev)),
+    };
+    let path = temp_path("wrong-shape.npz");
+    src.save_npz(&path).unwrap();
+    let err = dst.load_npz_on(&path, Some(&mut dev)).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(dst.pw.shape(), [1, 2].into());
+}
@@ -0,0 +1,324 @@
+#[macro_use]
+extern crate primitiv;
+
+use std::env;
+use std::fs;
+
+use primitiv::devices as D;
+use primitiv::node_functions as F;
+use primitiv::optimizers as O;
+use primitiv::Graph;
+use primitiv::Model;
+use primitiv::Optimizer;
+use primitiv::Parameter;
+
+#[derive(Model)]
+struct Embedding {
+    pw: Parameter,
+}
+
+#[derive(Model)]
+struct Classifier {
+    #[primitiv(submodel)]
+    embed: Embedding,
+    pw: Parameter,
+}
+
+#[derive(Model)]
+struct Decoder {
+    pw: Parameter,
+}
+
+#[derive(Model)]
+struct TiedModel {
+    #[primitiv(submodel)]
+    embed: Embedding,
+    #[primitiv(submodel)]
+    decoder: Decoder,
+}
+
+impl TiedModel {
+    fn new(dev: &mut D::Naive, values: &[f32]) -> Self {
+        let embed = Embedding {
+            pw: Parameter::from_values_on([2], values, Some(dev)),
+        };
+        // `embed` outlives the handle since both are owned by the same model.
+        let decoder = Decoder {
+            pw: unsafe { embed.pw.share() },
+        };
+        TiedModel { embed, decoder }
+    }
+}
+
+impl Classifier {
+    fn new(dev: &mut D::Naive) -> Self {
+        Classifier {
+            embed: Embedding {
+                pw: Parameter::from_values_on([2], &[1.0, 2.0], Some(dev)),
+            },
+            pw: Parameter::from_values_on([2], &[3.0, 4.0], Some(dev)),
+        }
+    }
+
+    fn set_gradients(&mut self) {
+        self.embed.pw.gradient().reset(1.0);
+        self.pw.gradient().reset(1.0);
+    }
+}
+
+#[test]
+fn frozen_parameter_is_not_updated() {
+    let mut dev = D::Naive::new();
+    let mut model = Classifier::new(&mut dev);
+    model.freeze(&["embed"]);
+    let mut optimizer = O::MomentumSGD::new(0.5, 0.9);
+    optimizer.add_model(&mut model);
+    assert!(!model.embed.pw.is_trainable());
+    assert!(model.pw.is_trainable());
+
+    model.set_gradients();
+    optimizer.update();
+    assert_eq!(model.embed.pw.value().to_vector(), vec![1.0, 2.0]);
+    assert_eq!(model.pw.value().to_vector(), vec![2.5, 3.5]);
+    assert_eq!(model.embed.pw.stats_names(), vec!["frozen"]);
+
+    model.unfreeze(&[]);
+    assert!(model.embed.pw.is_trainable());
+    model.set_gradients();
+    optimizer.update();
+    assert_eq!(model.embed.pw.value().to_vector(), vec![0.5, 1.5]);
+}
+
+#[test]
+fn frozen_parameter_added_directly() {
+    let mut dev = D::Naive::new();
+    let mut param = Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev));
+    param.set_trainable(false);
+    let mut optimizer = O::Adam::default();
+    optimizer.add_parameter(&mut param);
+    param.gradient().reset(1.0);
+    optimizer.update();
+    assert_eq!(param.value().to_vector(), vec![1.0, 2.0]);
+    assert_eq!(param.stats_names(), vec!["frozen"]);
+}
+
+#[test]
+fn freeze_after_adding_to_optimizer() {
+    let mut dev = D::Naive::new();
+    let mut model = Classifier::new(&mut dev);
+    let mut optimizer = O::MomentumSGD::new(0.5, 0.9);
+    optimizer.add_model(&mut model);
+    model.set_gradients();
+    optimizer.update();
+    assert_eq!(model.embed.pw.value().to_vector(), vec![0.5, 1.5]);
+
+    model.freeze(&["embed"]);
+    model.set_gradients();
+    optimizer.update();
+    assert_eq!(model.embed.pw.value().to_vector(), vec![0.5, 1.5]);
+    assert_eq!(
+        model.embed.pw.stats("momentumsgd-m").to_vector(),
+        vec![-0.5, -0.5]
+    );
+    assert_ne!(model.pw.value().to_vector(), vec![2.5, 3.5]);
+
+    model.unfreeze(&["embed"]);
+    model.set_gradients();
+    optimizer.update();
+    assert_ne!(model.embed.pw.value().to_vector(), vec![0.5, 1.5]);
+}
+
+#[test]
+fn frozen_flag_survives_save_and_load() {
+    let mut dev = D::Naive::new();
+    let mut model = Classifier::new(&mut dev);
+    model.register_parameters();
+    model.freeze(&["embed"]);
+    let path = env::temp_dir().join(format!(
+        "primitiv_frozen_model_test_{}.data",
+        std::process::id()
+    ));
+    model.save(&path, true).unwrap();
+    let mut loaded = Classifier::new(&mut dev);
+    loaded.load_on(&path, true, Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(!loaded.embed.pw.is_trainable());
+    assert!(loaded.pw.is_trainable());
+}
+
+#[test]
+#[should_panic(expected = "no parameter found at decoder")]
+fn freeze_unknown_path() {
+    let mut dev = D::Naive::new();
+    let mut model = Classifier::new(&mut dev);
+    model.freeze(&["decoder"]);
+}
+
+#[test]
+fn stats_of_parameter_are_enumerated() {
+    let mut dev = D::Naive::new();
+    let mut param = Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev));
+    assert!(param.stats_names().is_empty());
+    let mut optimizer = O::Adam::default();
+    optimizer.add_parameter(&mut param);
+    param.add_stats("ema", [2]);
+    assert_eq!(param.stats_names(), vec!["adam-m1", "adam-m2", "ema"]);
+
+    param.stats("ema").reset(1.0);
+    for (name, mut stats) in param.stats_iter() {
+        assert_eq!(stats.shape(), param.shape(), "{}", name);
+        if name != "ema" {
+            assert_eq!(stats.to_vector(), vec![0.0, 0.0]);
+        }
+        stats.reset(2.0);
+    }
+    assert_eq!(param.stats("adam-m1").to_vector(), vec![2.0, 2.0]);
+    assert_eq!(param.stats("ema").to_vector(), vec![2.0, 2.0]);
+}
+
+#[test]
+fn stats_of_each_optimizer_are_enumerated() {
+    fn stats_names<O: Optimizer>(mut optimizer: O) -> Vec<String> {
+        let mut dev = D::Naive::new();
+        let mut param = Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev));
+        optimizer.add_parameter(&mut param);
+        param.stats_names()
+    }
+    assert!(stats_names(O::SGD::default()).is_empty());
+    assert_eq!(
+        stats_names(O::MomentumSGD::default()),
+        vec!["momentumsgd-m"]
+    );
+    assert_eq!(stats_names(O::AdaGrad::default()), vec!["adagrad-m"]);
+    assert_eq!(stats_names(O::RMSProp::default()), vec!["rmsprop-m"]);
+    assert_eq!(
+        stats_names(O::AdaDelta::default()),
+        vec!["adadelta-m1", "adadelta-m2"]
+    );
+    assert_eq!(stats_names(O::Adam::default()), vec!["adam-m1", "adam-m2"]);
+}
+
+#[test]
+fn loaded_stats_are_enumerated() {
+    let mut dev = D::Naive::new();
+    let mut param = Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev));
+    param.add_stats("ema", [2]);
+    let path = env::temp_dir().join(format!(
+        "primitiv_stats_parameter_test_{}.data",
+        std::process::id()
+    ));
+    param.save(&path, true).unwrap();
+    let mut loaded = Parameter::new();
+    loaded.load_on(&path, true, Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.stats_names(), vec!["ema"]);
+
+    let mut model = Classifier::new(&mut dev);
+    model.register_parameters();
+    model.embed.pw.add_stats("ema", [2]);
+    let path = env::temp_dir().join(format!(
+        "primitiv_stats_model_test_{}.data",
+        std::process::id()
+    ));
+    model.save(&path, true).unwrap();
+    let mut loaded = Classifier::new(&mut dev);
+    loaded.load_on(&path, true, Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.embed.pw.stats_names(), vec!["ema"]);
+    assert!(loaded.pw.stats_names().is_empty());
+}
+
+#[test]
+fn tied_parameter_is_shared() {
+    let mut dev = D::Naive::new();
+    let mut model = TiedModel::new(&mut dev, &[1.0, 2.0]);
+    let mut optimizer = O::SGD::new(0.5);
+    optimizer.add_model(&mut model);
+    assert!(model.decoder.pw.is_shared());
+    assert_eq!(
+        model.get_all_parameters().keys().collect::<Vec<_>>(),
+        vec![&vec!["embed".to_string(), "pw".to_string()]]
+    );
+    let found = model.find_parameter(&["decoder", "pw"]).unwrap();
+    assert_eq!(found.value().to_vector(), vec![1.0, 2.0]);
+
+    model.decoder.pw.gradient().reset(1.0);
+    assert_eq!(model.embed.pw.gradient().to_vector(), vec![1.0, 1.0]);
+    optimizer.update();
+    assert_eq!(model.embed.pw.value().to_vector(), vec![0.5, 1.5]);
+    assert_eq!(model.decoder.pw.value().to_vector(), vec![0.5, 1.5]);
+
+    let path = env::temp_dir().join(format!(
+        "primitiv_tied_model_test_{}.data",
+        std::process::id()
+    ));
+    model.save(&path, false).unwrap();
+    let mut loaded = TiedModel::new(&mut dev, &[0.0, 0.0]);
+    loaded.load_on(&path, false, Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.decoder.pw.value().to_vector(), vec![0.5, 1.5]);
+}
+
+#[test]
+fn accumulated_gradients_are_averaged() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let mut param = Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev));
+    let mut optimizer = O::SGD::new(0.5);
+    optimizer.add_parameter(&mut param);
+    optimizer.reset_gradients();
+    for data in &[[1.0, 2.0], [3.0, 4.0]] {
+        g.clear();
+        let w = F::parameter_into(&mut param, Some(&mut g));
+        let x = F::input_into([2], data, Some(&mut dev), Some(&mut g));
+        F::sum(F::multiply(&w, &x), 0).backward();
+        optimizer.accumulate();
+    }
+    assert_eq!(optimizer.get_accumulated_steps(), 2);
+    assert_eq!(param.gradient().to_vector(), vec![4.0, 6.0]);
+
+    optimizer.update_accumulated();
+    assert_eq!(param.value().to_vector(), vec![0.0, 0.5]);
+    assert_eq!(param.gradient().to_vector(), vec![0.0, 0.0]);
+    assert_eq!(optimizer.get_accumulated_steps(), 0);
+}
+
+#[test]
+#[should_panic(expected = "no micro-batch has been accumulated")]
+fn update_without_accumulation() {
+    let mut optimizer = O::SGD::new(0.5);
+    optimizer.update_accumulated();
+}
+
+#[test]
+fn gradients_are_clipped_by_global_norm() {
+    let mut dev = D::Naive::new();
+    let mut model = Classifier::new(&mut dev);
+    let mut optimizer = O::SGD::new(0.5);
+    optimizer.add_model(&mut model);
+    model.embed.pw.gradient().reset_by_slice(&[3.0, 0.0]);
+    model.pw.gradient().reset_by_slice(&[0.0, 4.0]);
+    assert_eq!(model.grad_norm(), 5.0);
+
+    assert_eq!(optimizer.clip_grad_norm(10.0), 5.0);
+    assert_eq!(model.pw.gradient().to_vector(), vec![0.0, 4.0]);
+    assert_eq!(optimizer.clip_grad_norm(2.5), 5.0);
+    assert_eq!(model.embed.pw.gradient().to_vector(), vec![1.5, 0.0]);
+    assert_eq!(model.pw.gradient().to_vector(), vec![0.0, 2.0]);
+    assert_eq!(model.grad_norm(), 2.5);
+}
+
+#[test]
+fn global_norm_excludes_frozen_parameters() {
+    let mut dev = D::Naive::new();
+    let mut model = Classifier::new(&mut dev);
+    model.freeze(&["embed"]);
+    let mut optimizer = O::SGD::new(0.5);
+    optimizer.add_model(&mut model);
+    model.embed.pw.gradient().reset_by_slice(&[30.0, 0.0]);
+    model.pw.gradient().reset_by_slice(&[3.0, 4.0]);
+    assert_eq!(model.grad_norm(), 5.0);
+    assert_eq!(optimizer.clip_grad_norm(2.5), 5.0);
+    assert_eq!(model.embed.pw.gradient().to_vector(), vec![30.0, 0.0]);
+    assert_eq!(model.grad_norm(), 2.5);
+}
@@ -0,0 +1,99 @@
+#[macro_use]
+extern crate primitiv;
+
+use std::env;
+use std::fs;
+
+use primitiv::devices as D;
+use primitiv::optimizers as O;
+use primitiv::prune;
+use primitiv::Model;
+use primitiv::Optimizer;
+use primitiv::Parameter;
+
+#[derive(Model)]
+struct Linear {
+    pw: Parameter,
+    pb: Parameter,
+}
+
+impl Linear {
+    fn new(dev: &mut D::Naive) -> Self {
+        Linear {
+            pw: Parameter::from_values_on([2, 2], &[0.1, -3.0, 0.2, 4.0], Some(&mut *dev)),
+            pb: Parameter::from_values_on([2], &[1.0, 2.0], Some(dev)),
+        }
+    }
+}
+
+#[test]
+fn magnitude_pruning_masks_small_weights() {
+    let mut dev = D::Naive::new();
+    let mut model = Linear::new(&mut dev);
+    let sparsity = prune::magnitude(&mut model, 0.5);
+    assert_eq!(sparsity.len(), 1);
+    assert_eq!(sparsity[&vec!["pw".to_string()]], 0.5);
+    assert_eq!(model.pw.value().to_vector(), vec![0.0, -3.0, 0.0, 4.0]);
+    assert_eq!(
+        model.pw.stats(prune::MASK_STATS).to_vector(),
+        vec![0.0, 1.0, 0.0, 1.0]
+    );
+    assert_eq!(model.pb.value().to_vector(), vec![1.0, 2.0]);
+    assert!(!model.pb.has_stats(prune::MASK_STATS));
+    assert_eq!(prune::sparsity(&mut model), sparsity);
+
+    // Pruned weights are kept pruned even with a lower sparsity.
+    assert_eq!(prune::magnitude_parameter(&mut model.pw, 0.25), 0.5);
+    assert_eq!(prune::magnitude_parameter(&mut model.pw, 0.75), 0.75);
+    assert_eq!(model.pw.value().to_vector(), vec![0.0, 0.0, 0.0, 4.0]);
+}
+
+#[test]
+fn masks_are_applied_after_update() {
+    let mut dev = D::Naive::new();
+    let mut model = Linear::new(&mut dev);
+    let mut optimizer = O::SGD::new(1.0);
+    optimizer.add_model(&mut model);
+    prune::magnitude_parameter(&mut model.pw, 0.5);
+    model.pw.gradient().reset(-1.0);
+    model.pb.gradient().reset(-1.0);
+    optimizer.update();
+    assert_eq!(model.pw.value().to_vector(), vec![0.0, -2.0, 0.0, 5.0]);
+    assert_eq!(model.pb.value().to_vector(), vec![2.0, 3.0]);
+}
+
+#[test]
+fn masks_survive_save_and_load() {
+    let mut dev = D::Naive::new();
+    let mut model = Linear::new(&mut dev);
+    prune::magnitude(&mut model, 0.5);
+    let path = env::temp_dir().join(format!(
+        "primitiv_pruned_model_test_{}.data",
+        std::process::id()
+    ));
+    model.save(&path, true).unwrap();
+
+    let mut loaded = Linear::new(&mut dev);
+    loaded.load_on(&path, true, Some(&mut dev)).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(prune::sparsity(&mut loaded), prune::sparsity(&mut model));
+    assert!(loaded
+        .pw
+        .stats_names()
+        .contains(&prune::MASK_STATS.to_string()));
+
+    let mut optimizer = O::SGD::new(1.0);
+    optimizer.add_model(&mut loaded);
+    loaded.pw.gradient().reset(-1.0);
+    loaded.pb.gradient().reset(0.0);
+    optimizer.update();
+    assert_eq!(loaded.pw.value().to_vector(), vec![0.0, -2.0, 0.0, 5.0]);
+}
+
+#[test]
+#[should_panic(expected = "sparsity must be in [0, 1]: 1.5")]
+fn invalid_sparsity() {
+    let mut dev = D::Naive::new();
+    let mut model = Linear::new(&mut dev);
+    prune::magnitude(&mut model, 1.5);
+}
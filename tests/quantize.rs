@@ -0,0 +1,150 @@
+#[macro_use]
+extern crate primitiv;
+
+use std::env;
+use std::fs;
+
+use primitiv::devices as D;
+use primitiv::quantize::{Granularity, QuantizedModel, QuantizedTensor};
+use primitiv::tensor_functions as F;
+use primitiv::Model;
+use primitiv::Parameter;
+use primitiv::Tensor;
+
+const ROWS: u32 = 4;
+const COLS: u32 = 8;
+
+#[derive(Model)]
+struct Linear {
+    pw: Parameter,
+    pb: Parameter,
+}
+
+impl Linear {
+    fn new(dev: &mut D::Naive) -> Self {
+        // Rows have different magnitudes, which makes per-row scales more accurate.
+        let w = (0..ROWS * COLS)
+            .map(|i| {
+                let row = i % ROWS;
+                ((i * 7 % 13) as f32 - 6.0) / 6.0 * 10f32.powi(row as i32 - 2)
+            })
+            .collect::<Vec<_>>();
+        let b = (0..ROWS).map(|i| i as f32 / 4.0).collect::<Vec<_>>();
+        Linear {
+            pw: Parameter::from_values_on([ROWS, COLS], &w, Some(&mut *dev)),
+            pb: Parameter::from_values_on([ROWS], &b, Some(dev)),
+        }
+    }
+
+    fn forward(&self, x: &Tensor) -> Tensor {
+        F::add(F::matmul(self.pw.value(), x), self.pb.value())
+    }
+}
+
+fn max_error(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).abs())
+        .fold(0.0, f32::max)
+}
+
+fn make_input(dev: &mut D::Naive) -> Tensor {
+    let x = (0..COLS).map(|i| 1.0 - i as f32 / 4.0).collect::<Vec<_>>();
+    F::input_on([COLS], &x, Some(dev))
+}
+
+#[test]
+fn quantization_error_is_bounded_by_scale() {
+    let mut dev = D::Naive::new();
+    let model = Linear::new(&mut dev);
+    let w = model.pw.value();
+    let values = w.to_vector();
+    let per_tensor = QuantizedTensor::new(&w, Granularity::PerTensor);
+    let per_row = QuantizedTensor::new(&w, Granularity::PerRow);
+    assert_eq!(per_tensor.scales().len(), 1);
+    assert_eq!(per_row.scales().len(), ROWS as usize);
+    assert_eq!(per_row.shape(), w.shape());
+
+    let scale = per_tensor.scales()[0];
+    assert!(max_error(&per_tensor.dequantize(), &values) <= scale / 2.0 + 1e-6);
+    for (i, (x, y)) in per_row.dequantize().iter().zip(&values).enumerate() {
+        let scale = per_row.scales()[i % ROWS as usize];
+        assert!((x - y).abs() <= scale / 2.0 + 1e-6);
+    }
+    let first_row = |xs: Vec<f32>| {
+        xs.into_iter()
+            .enumerate()
+            .filter(|&(i, _)| i % ROWS as usize == 0)
+            .map(|(_, x)| x)
+            .collect::<Vec<_>>()
+    };
+    let values = first_row(values);
+    assert!(
+        max_error(&first_row(per_row.dequantize()), &values)
+            < max_error(&first_row(per_tensor.dequantize()), &values)
+    );
+
+    let zeros = F::zeros_on([3], Some(&mut dev));
+    let quantized = QuantizedTensor::new(&zeros, Granularity::PerTensor);
+    assert_eq!(quantized.values(), &[0, 0, 0]);
+    assert_eq!(quantized.dequantize(), vec![0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn quantized_matmul_matches_float_matmul() {
+    let mut dev = D::Naive::new();
+    let model = Linear::new(&mut dev);
+    let quantized = QuantizedTensor::new(&model.pw.value(), Granularity::PerRow);
+    let x = F::input_on(([COLS], 2), &[0.5; 2 * COLS as usize], Some(&mut dev));
+    let y = quantized.matmul(&x);
+    assert_eq!(y.shape(), F::matmul(model.pw.value(), &x).shape());
+    assert_tensor_close!(y, F::matmul(quantized.to_tensor_on(Some(&mut dev)), &x));
+}
+
+#[test]
+fn quantized_model_is_close_to_float_model() {
+    let mut dev = D::Naive::new();
+    let mut model = Linear::new(&mut dev);
+    let x = make_input(&mut dev);
+    let expected = model.forward(&x).to_vector();
+    let norm = expected.iter().fold(0.0, |m: f32, y| m.max(y.abs()));
+
+    for &granularity in &[Granularity::PerTensor, Granularity::PerRow] {
+        let path = env::temp_dir().join(format!(
+            "primitiv_quantized_model_test_{}.npz",
+            std::process::id()
+        ));
+        QuantizedModel::new(&mut model, granularity)
+            .save(&path)
+            .unwrap();
+        let mut loaded = Linear::new(&mut dev);
+        loaded.pw.value().reset(0.0);
+        QuantizedModel::load(&path)
+            .unwrap()
+            .dequantize_into_on(&mut loaded, Some(&mut dev))
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        let actual = loaded.forward(&x).to_vector();
+        assert!(max_error(&actual, &expected) < 0.01 * norm);
+    }
+}
+
+#[test]
+fn quantized_model_requires_all_parameters() {
+    #[derive(Model)]
+    struct Single {
+        pw: Parameter,
+    }
+
+    let mut dev = D::Naive::new();
+    let mut model = Single {
+        pw: Parameter::from_values_on([2], &[1.0, -1.0], Some(&mut dev)),
+    };
+    let quantized = QuantizedModel::new(&mut model, Granularity::PerTensor);
+    assert_eq!(quantized.get(&["pw"]).unwrap().values(), &[127, -127]);
+
+    let mut other = Linear::new(&mut dev);
+    assert!(quantized
+        .dequantize_into_on(&mut other, Some(&mut dev))
+        .is_err());
+}
@@ -10,10 +10,15 @@ extern crate serde_json;
 #[cfg(feature = "serialize")]
 mod tests {
     use serde_json;
+    use std::env;
+    use std::fs;
 
+    use primitiv::devices as D;
+    use primitiv::tensor_functions as F;
     use primitiv::Model;
     use primitiv::Node;
     use primitiv::Parameter;
+    use primitiv::Shape;
     use primitiv::Tensor;
 
     #[derive(Model, Serialize, Deserialize)]
@@ -53,20 +58,20 @@ mod tests {
             let node = Node::new();
             let serialized = serde_json::to_string(&node).unwrap();
             assert_eq!("{}", serialized);
-            let deserialized: Tensor = serde_json::from_str(&serialized).unwrap();
+            let deserialized: Node = serde_json::from_str(&serialized).unwrap();
             assert!(!deserialized.valid());
         }
         {
             let param = Parameter::new();
             let serialized = serde_json::to_string(&param).unwrap();
-            assert_eq!("{}", serialized);
+            assert_eq!("null", serialized);
             let deserialized: Parameter = serde_json::from_str(&serialized).unwrap();
             assert!(!deserialized.valid());
         }
         {
             let tensor = Tensor::new();
             let serialized = serde_json::to_string(&tensor).unwrap();
-            assert_eq!("{}", serialized);
+            assert_eq!("null", serialized);
             let deserialized: Tensor = serde_json::from_str(&serialized).unwrap();
             assert!(!deserialized.valid());
         }
@@ -78,7 +83,7 @@ mod tests {
             assert!(model.get_parameter("pw1").is_some());
             assert!(model.get_parameter("pw2").is_some());
             let serialized = serde_json::to_string(&model).unwrap();
-            assert_eq!("{\"pw1\":{},\"pw2\":{}}", serialized);
+            assert_eq!("{\"pw1\":null,\"pw2\":null}", serialized);
             let mut deserialized: Model1 = serde_json::from_str(&serialized).unwrap();
             assert!(deserialized.get_parameter("pw1").is_none());
             assert!(deserialized.get_parameter("pw2").is_none());
@@ -86,7 +91,7 @@ mod tests {
             assert!(deserialized.get_parameter("pw1").is_some());
             assert!(deserialized.get_parameter("pw2").is_some());
             let serialized = serde_json::to_string(&deserialized).unwrap();
-            assert_eq!("{\"pw1\":{},\"pw2\":{}}", serialized);
+            assert_eq!("{\"pw1\":null,\"pw2\":null}", serialized);
         }
         {
             let mut model = Model2::new();
@@ -100,7 +105,10 @@ mod tests {
             assert!(model.find_parameter(&["model1", "pw2"]).is_some());
             assert!(model.get_parameter("pw").is_some());
             let serialized = serde_json::to_string(&model).unwrap();
-            assert_eq!("{\"model1\":{\"pw1\":{},\"pw2\":{}},\"pw\":{}}", serialized);
+            assert_eq!(
+                "{\"model1\":{\"pw1\":null,\"pw2\":null},\"pw\":null}",
+                serialized
+            );
             let mut deserialized: Model2 = serde_json::from_str(&serialized).unwrap();
             assert!(deserialized.get_submodel("model1").is_none());
             assert!(deserialized.find_parameter(&["model1", "pw1"]).is_none());
@@ -112,7 +120,85 @@ mod tests {
             assert!(deserialized.find_parameter(&["model1", "pw2"]).is_some());
             assert!(deserialized.get_parameter("pw").is_some());
             let serialized = serde_json::to_string(&deserialized).unwrap();
-            assert_eq!("{\"model1\":{\"pw1\":{},\"pw2\":{}},\"pw\":{}}", serialized);
+            assert_eq!(
+                "{\"model1\":{\"pw1\":null,\"pw2\":null},\"pw\":null}",
+                serialized
+            );
+        }
+    }
+
+    #[test]
+    fn shape_serde_test() {
+        let shape = Shape::from(([2, 3], 4));
+        let serialized = serde_json::to_string(&shape).unwrap();
+        assert_eq!("{\"dims\":[2,3],\"batch\":4}", serialized);
+        let deserialized: Shape = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(shape, deserialized);
+        let deserialized: Shape = serde_json::from_str("[[5],1]").unwrap();
+        assert_eq!(Shape::from([5]), deserialized);
+        assert!(serde_json::from_str::<Shape>("{\"dims\":[2]}").is_err());
+        assert!(serde_json::from_str::<Shape>("[[2,0],1]").is_err());
+        assert!(serde_json::from_str::<Shape>("[[2],0]").is_err());
+        assert!(serde_json::from_str::<Shape>("[[1,1,1,1,1,1,1,1,1],1]").is_err());
+    }
+
+    #[test]
+    fn serde_values_test() {
+        let mut dev = D::Naive::new();
+        D::set_default(&mut dev);
+        {
+            let tensor = F::input(([2], 2), &[1.0, 2.0, 3.0, 4.0]);
+            let serialized = serde_json::to_string(&tensor).unwrap();
+            assert_eq!(
+                "{\"dims\":[2],\"batch\":2,\"values\":[1.0,2.0,3.0,4.0]}",
+                serialized
+            );
+            let deserialized: Tensor = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized.shape(), tensor.shape());
+            assert_eq!(deserialized.to_vector(), tensor.to_vector());
+            let invalid = "{\"dims\":[3],\"batch\":1,\"values\":[1.0]}";
+            assert!(serde_json::from_str::<Tensor>(invalid).is_err());
+            let invalid = "{\"dims\":[0],\"batch\":1,\"values\":[]}";
+            assert!(serde_json::from_str::<Tensor>(invalid).is_err());
+            let invalid = "{\"dims\":[1],\"batch\":0,\"values\":[]}";
+            assert!(serde_json::from_str::<Tensor>(invalid).is_err());
+        }
+        {
+            let mut param = Parameter::from_values([2], &[1.0, 2.0]);
+            param.gradient().reset(0.5);
+            param.add_stats("ema", [2]);
+            param.stats("ema").reset_by_slice(&[3.0, 4.0]);
+            param.set_trainable(false);
+            let serialized = serde_json::to_string(&param).unwrap();
+            let deserialized: Parameter = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(deserialized.value().to_vector(), vec![1.0, 2.0]);
+            assert_eq!(deserialized.gradient().to_vector(), vec![0.5, 0.5]);
+            assert_eq!(deserialized.stats("ema").to_vector(), vec![3.0, 4.0]);
+            assert!(!deserialized.is_trainable());
+
+            let path = env::temp_dir().join(format!(
+                "primitiv_serde_parameter_test_{}.data",
+                std::process::id()
+            ));
+            param.save(&path, true).unwrap();
+            let mut loaded = Parameter::new();
+            loaded.load(&path, true).unwrap();
+            fs::remove_file(&path).unwrap();
+            let deserialized: Parameter =
+                serde_json::from_str(&serde_json::to_string(&loaded).unwrap()).unwrap();
+            assert_eq!(deserialized.stats("ema").to_vector(), vec![3.0, 4.0]);
+        }
+        {
+            let mut model = Model2::new();
+            model.model1.pw1.init_by_values([1], &[1.0]);
+            model.model1.pw2.init_by_values([1], &[2.0]);
+            model.pw.init_by_values([2], &[3.0, 4.0]);
+            let serialized = serde_json::to_string(&model).unwrap();
+            let mut deserialized: Model2 = serde_json::from_str(&serialized).unwrap();
+            deserialized.register_parameters();
+            let pw = deserialized.find_parameter(&["model1", "pw2"]).unwrap();
+            assert_eq!(pw.value().to_vector(), vec![2.0]);
+            assert_eq!(deserialized.pw.value().to_vector(), vec![3.0, 4.0]);
         }
     }
 }
@@ -0,0 +1,150 @@
+extern crate primitiv;
+
+use std::collections::HashSet;
+
+use primitiv::concat_shape;
+use primitiv::Shape;
+
+#[test]
+fn shape_conversion_test() {
+    assert_eq!(Shape::from(vec![2, 3]), Shape::from([2, 3]));
+    let dims: &[u32] = &[4, 1, 5];
+    assert_eq!(Shape::from(dims), Shape::from([4, 1, 5]));
+}
+
+#[test]
+fn shape_index_and_iter_test() {
+    let mut shape = Shape::from(([2, 3, 4], 5));
+    assert_eq!(shape[0], 2);
+    assert_eq!(shape[2], 4);
+    assert_eq!(shape[3], 1);
+    assert_eq!(shape[100], 1);
+    assert_eq!(shape.iter().cloned().collect::<Vec<_>>(), vec![2, 3, 4]);
+    shape.update_dim(1, 6);
+    assert_eq!(shape[1], 6);
+    assert_eq!((&shape).into_iter().product::<u32>(), shape.volume());
+    let mut other = Shape::new();
+    other.clone_from(&shape);
+    assert_eq!(other[1], 6);
+}
+
+#[test]
+fn shape_hash_and_ord_test() {
+    let mut set = HashSet::new();
+    set.insert(Shape::from([2, 3]));
+    set.insert(Shape::from([2, 3, 1]));
+    set.insert(Shape::from(([2, 3], 2)));
+    assert_eq!(set.len(), 2);
+
+    let mut shapes = vec![
+        Shape::from([3]),
+        Shape::from(([2, 3], 2)),
+        Shape::from([2, 3]),
+        Shape::new(),
+    ];
+    shapes.sort();
+    assert_eq!(
+        shapes,
+        vec![
+            Shape::new(),
+            Shape::from([2, 3]),
+            Shape::from(([2, 3], 2)),
+            Shape::from([3]),
+        ]
+    );
+}
+
+#[test]
+fn shape_broadcast_test() {
+    assert_eq!(
+        Shape::broadcast(&Shape::from([2, 3]), &Shape::from(([2, 3], 4))),
+        Some(Shape::from(([2, 3], 4)))
+    );
+    assert_eq!(
+        Shape::broadcast(&Shape::from(([2, 3, 4], 5)), &Shape::from([2, 3, 4])),
+        Some(Shape::from(([2, 3, 4], 5)))
+    );
+    assert_eq!(
+        Shape::broadcast(&Shape::from([2, 1]), &Shape::from([2, 3])),
+        None
+    );
+    assert_eq!(
+        Shape::broadcast(&Shape::from([2, 3, 4]), &Shape::new()),
+        None
+    );
+    assert_eq!(
+        Shape::broadcast(&Shape::from([2, 3]), &Shape::from([3, 3])),
+        None
+    );
+    assert_eq!(
+        Shape::broadcast(&Shape::from(([2], 2)), &Shape::from(([2], 3))),
+        None
+    );
+}
+
+#[test]
+fn concat_shape_test() {
+    let a = Shape::from([2, 3]);
+    let b = Shape::from(([2, 5], 4));
+    assert_eq!(concat_shape(&[&a, &b], 1), Some(Shape::from(([2, 8], 4))));
+    assert_eq!(concat_shape(&[&a, &a, &a], 2), Some(Shape::from([2, 3, 3])));
+    assert_eq!(concat_shape(&[&a, &b], 0), None);
+    assert_eq!(concat_shape(&[], 0), None);
+    assert_eq!(concat_shape(&[&a, &a], 8), None);
+}
+
+#[test]
+fn shape_value_test() {
+    let a = Shape::from(([2, 3, 1], 4));
+    let b = a;
+    assert_eq!(a, b);
+    assert_eq!(a.dims(), vec![2, 3]);
+    assert_eq!(a.depth(), 2);
+    assert_eq!(a.volume(), 6);
+    assert_eq!(a.lower_volume(1), 2);
+    assert_eq!(a.size(), 24);
+    assert_eq!(format!("{}", a), "[2,3]x4");
+    assert_eq!(format!("{}", Shape::new()), "[]x1");
+
+    let c = a.resize_dim(4, 5);
+    assert_eq!(c.dims(), vec![2, 3, 1, 1, 5]);
+    assert_eq!(c.resize_dim(4, 1), a);
+    assert!(a.has_same_loo_dims(&c, 4));
+    assert!(!a.has_same_dims(&c));
+}
+
+#[test]
+#[should_panic]
+fn shape_zero_dim_test() {
+    let _ = Shape::from([2, 0]);
+}
+
+#[test]
+#[should_panic]
+fn shape_too_deep_test() {
+    let _ = Shape::from(vec![1; 9]);
+}
+
+#[test]
+fn shape_names_test() {
+    let shape = Shape::from([3, 5]).with_names(&["hidden", "time"]);
+    assert!(shape.has_names());
+    assert_eq!(shape.name(0), Some("hidden"));
+    assert_eq!(shape.name(2), None);
+    assert_eq!(shape.dim_by_name("time"), Some(1));
+    assert_eq!(shape.dim_by_name("batch"), None);
+    assert_eq!(shape, Shape::from([3, 5]));
+    assert_eq!(shape.resize_dim(1, 2).name(1), Some("time"));
+    assert!(!Shape::from([3, 5]).has_names());
+
+    let names = vec!["hidden".to_string(), format!("time{}", 0)];
+    let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+    let shape = Shape::from([3, 5]).with_names(&names);
+    assert_eq!(shape.dim_by_name("time0"), Some(1));
+}
+
+#[test]
+#[should_panic(expected = "duplicated dimension name: time")]
+fn shape_duplicated_names_test() {
+    let _ = Shape::from([3, 5]).with_names(&["time", "time"]);
+}
@@ -0,0 +1,293 @@
+#[macro_use]
+extern crate primitiv;
+
+use primitiv::devices as D;
+use primitiv::tensor_functions as F;
+use primitiv::Device;
+
+#[test]
+fn device_kind_is_recorded() {
+    let dev = D::Naive::new();
+    assert_eq!(dev.kind(), D::DeviceKind::Naive);
+}
+
+#[test]
+fn display_lays_out_matrix_by_rows() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on([2, 3], &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0], Some(&mut dev));
+    assert_eq!(
+        format!("{}", x),
+        "Tensor(shape=[2,3]x1, device=Naive)\n[[1, 2, 3],\n [4, 5, 6]]"
+    );
+}
+
+#[test]
+fn display_puts_batch_outermost() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on(([2], 2), &[1.0, 2.0, 3.0, 4.0], Some(&mut dev));
+    assert_eq!(
+        format!("{}", x),
+        "Tensor(shape=[2]x2, device=Naive)\n[[1, 2],\n [3, 4]]"
+    );
+}
+
+#[test]
+fn display_elides_large_tensor() {
+    let mut dev = D::Naive::new();
+    let values: Vec<f32> = (0..2000).map(|i| (i % 10) as f32).collect();
+    let x = F::input_on([2000], &values, Some(&mut dev));
+    assert_eq!(
+        format!("{}", x),
+        "Tensor(shape=[2000]x1, device=Naive)\n[0, 1, 2, ..., 7, 8, 9]"
+    );
+}
+
+#[test]
+fn debug_shows_summary() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on([4], &[1.0, 2.0, 3.0, 6.0], Some(&mut dev));
+    assert_eq!(
+        format!("{:?}", x),
+        "Tensor { shape: [4]x1, min: 1.0, max: 6.0, mean: 3.0 }"
+    );
+    assert_eq!(
+        format!("{:?}", primitiv::Tensor::new()),
+        "Tensor { valid: false }"
+    );
+}
+
+#[test]
+fn from_batch_concatenates_samples() {
+    let mut dev = D::Naive::new();
+    let samples = vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![5.0, 6.0]];
+    let x = primitiv::Tensor::from_batch_on(&samples, [2], Some(&mut dev));
+    assert_eq!(x.shape().dims(), vec![2]);
+    assert_eq!(x.shape().batch(), 3);
+    assert_eq!(x.to_vector(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    assert_eq!(x.to_batches(), samples);
+}
+
+#[test]
+#[should_panic(expected = "sample 1 has 3 values")]
+fn from_batch_with_wrong_volume_panics() {
+    let mut dev = D::Naive::new();
+    let samples: Vec<&[f32]> = vec![&[1.0, 2.0], &[3.0, 4.0, 5.0]];
+    F::input_batch_on(&samples, [2], Some(&mut dev));
+}
+
+#[test]
+fn allclose_and_max_abs_diff() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on([3], &[1.0, 2.0, 3.0], Some(&mut dev));
+    let y = F::input_on([3], &[1.0, 2.0, 3.001], Some(&mut dev));
+    let z = F::input_on([1, 3], &[1.0, 2.0, 3.0], Some(&mut dev));
+    assert!(x.allclose(&y, 1e-3, 0.0));
+    assert!(!x.allclose(&y, 1e-5, 1e-8));
+    assert!(!x.allclose(&z, 1.0, 1.0));
+    assert!((x.max_abs_diff(&y) - 0.001).abs() < 1e-6);
+}
+
+#[test]
+fn assert_tensor_close_passes() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on([2], &[1.0, 2.0], Some(&mut dev));
+    let y = F::input_on([2], &[1.0, 2.0000001], Some(&mut dev));
+    assert_tensor_close!(x, y);
+    assert_tensor_close!(x, y, 0.0, 1e-3);
+}
+
+#[test]
+#[should_panic(expected = "first mismatch at index [1] of batch 1")]
+fn assert_tensor_close_reports_first_mismatch() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on(([2], 2), &[1.0, 2.0, 3.0, 4.0], Some(&mut dev));
+    let y = F::input_on(([2], 2), &[1.0, 2.0, 3.0, 5.0], Some(&mut dev));
+    assert_tensor_close!(x, y);
+}
+
+#[test]
+fn get_elements() {
+    let mut dev = D::Naive::new();
+    let values: Vec<f32> = (0..12).map(|i| i as f32).collect();
+    let x = F::input_on(([2, 3], 2), &values, Some(&mut dev));
+    assert_eq!(x.get(&[0, 0], 0), 0.0);
+    assert_eq!(x.get(&[1, 2], 0), 5.0);
+    assert_eq!(x.get(&[1], 1), 7.0);
+    assert_eq!(x.get(&[0, 1, 0], 1), 8.0);
+}
+
+#[test]
+fn set_element() {
+    let mut dev = D::Naive::new();
+    let values: Vec<f32> = (0..12).map(|i| i as f32).collect();
+    let mut x = F::input_on(([2, 3], 2), &values, Some(&mut dev));
+    x.set(&[0, 1], 1, -1.0);
+    assert_eq!(x.get(&[0, 1], 1), -1.0);
+    assert_eq!(x.to_vector()[8], -1.0);
+    assert_eq!(x.to_vector()[2], 2.0);
+}
+
+#[test]
+#[should_panic(expected = "batch index 2 is out of range")]
+fn set_out_of_range_panics() {
+    let mut dev = D::Naive::new();
+    let mut x = F::input_on(([2, 3], 2), &[0.0; 12], Some(&mut dev));
+    x.set(&[0, 0], 2, 1.0);
+}
+
+#[test]
+#[should_panic(expected = "index 3 is out of range for dimension 1")]
+fn get_out_of_range_panics() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on([2, 3], &[0.0; 6], Some(&mut dev));
+    x.get(&[0, 3], 0);
+}
+
+#[test]
+fn get_range_slices_dimensions_and_batch() {
+    let mut dev = D::Naive::new();
+    let values: Vec<f32> = (0..12).map(|i| i as f32).collect();
+    let x = F::input_on(([2, 3], 2), &values, Some(&mut dev));
+    let y = x.get_range(&[1..2, 0..2], 1..2);
+    assert_eq!(y.shape().dims(), vec![1, 2]);
+    assert_eq!(y.shape().batch(), 1);
+    assert_eq!(y.to_vector(), vec![7.0, 9.0]);
+}
+
+#[test]
+fn compound_assignment_operators() {
+    let mut dev = D::Naive::new();
+    let mut x = F::input_on([2], &[1.0, 2.0], Some(&mut dev));
+    let y = F::input_on([2], &[3.0, 5.0], Some(&mut dev));
+    x += &y;
+    assert_eq!(x.to_vector(), vec![4.0, 7.0]);
+    x -= y;
+    assert_eq!(x.to_vector(), vec![1.0, 2.0]);
+    x *= 4.0;
+    assert_eq!(x.to_vector(), vec![4.0, 8.0]);
+    x /= 2.0;
+    assert_eq!(x.to_vector(), vec![2.0, 4.0]);
+}
+
+#[test]
+fn inplace_elementwise_operations() {
+    let mut dev = D::Naive::new();
+    let mut x = F::input_on([3], &[1.0, -2.0, 3.0], Some(&mut dev));
+    let y = F::input_on([3], &[2.0, 2.0, 0.5], Some(&mut dev));
+    x.inplace_multiply(&y);
+    assert_eq!(x.to_vector(), vec![2.0, -4.0, 1.5]);
+    x.inplace_divide(&y);
+    assert_eq!(x.to_vector(), vec![1.0, -2.0, 3.0]);
+    x.inplace_axpy(2.0, &y);
+    assert_eq!(x.to_vector(), vec![5.0, 2.0, 4.0]);
+    x.inplace_clamp(2.5, 4.5);
+    assert_eq!(x.to_vector(), vec![4.5, 2.5, 4.0]);
+    x.reset_by_tensor(&y);
+    assert_eq!(x.to_vector(), vec![2.0, 2.0, 0.5]);
+    x.reset_by_initializer(&primitiv::initializers::Constant::new(7.0));
+    assert_eq!(x.to_vector(), vec![7.0, 7.0, 7.0]);
+}
+
+#[test]
+fn inplace_clamp_is_exact() {
+    let mut dev = D::Naive::new();
+    let values = [0.1, -2.9, 1.0 / 3.0, 2.999];
+    let mut x = F::input_on([4], &values, Some(&mut dev));
+    x.inplace_clamp(-3.0, 3.0);
+    assert_eq!(x.to_vector(), values.to_vec());
+
+    let mut x = F::input_on([3], &[5.0, -1e30, 0.25], Some(&mut dev));
+    x.inplace_clamp(1.0, ::std::f32::MAX);
+    assert_eq!(x.to_vector(), vec![5.0, 1.0, 1.0]);
+    x.inplace_clamp(::std::f32::MIN, ::std::f32::MAX);
+    assert_eq!(x.to_vector(), vec![5.0, 1.0, 1.0]);
+}
+
+#[test]
+fn inplace_update_keeps_parameter_alias() {
+    let mut dev = D::Naive::new();
+    let param = primitiv::Parameter::from_values_on([2], &[1.0, 2.0], Some(&mut dev));
+    let target = F::input_on([2], &[3.0, 6.0], Some(&mut dev));
+    // Polyak averaging: v = 0.5 * v + 0.5 * target
+    let mut value = param.value();
+    value *= 0.5;
+    value.inplace_axpy(0.5, &target);
+    assert_eq!(param.value().to_vector(), vec![2.0, 4.0]);
+}
+
+#[test]
+fn stats_over_all_minibatches() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on(([2], 2), &[1.0, 2.0, 3.0, 6.0], Some(&mut dev));
+    let stats = x.stats();
+    assert_eq!(stats.min, 1.0);
+    assert_eq!(stats.max, 6.0);
+    assert_eq!(stats.mean, 3.0);
+    assert!((stats.std - 3.5f32.sqrt()).abs() < 1e-6);
+    assert_eq!(stats.nan_count, 0);
+    assert_eq!(stats.inf_count, 0);
+    assert!(!stats.has_non_finite());
+}
+
+#[test]
+fn stats_counts_non_finite_values() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on(
+        [5],
+        &[
+            1.0,
+            ::std::f32::NAN,
+            3.0,
+            ::std::f32::INFINITY,
+            ::std::f32::NEG_INFINITY,
+        ],
+        Some(&mut dev),
+    );
+    let stats = x.stats();
+    assert_eq!(stats.min, 1.0);
+    assert_eq!(stats.max, 3.0);
+    assert_eq!(stats.mean, 2.0);
+    assert_eq!(stats.std, 1.0);
+    assert_eq!(stats.nan_count, 1);
+    assert_eq!(stats.inf_count, 2);
+    assert!(stats.has_non_finite());
+}
+
+#[test]
+fn histogram_test() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on([6], &[0.0, 1.0, 1.5, 2.0, 3.5, 4.0], Some(&mut dev));
+    let hist = x.histogram(4);
+    assert_eq!(hist.edges, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(hist.counts, vec![1, 2, 1, 2]);
+
+    let y = F::input_on([3], &[2.0, 2.0, ::std::f32::NAN], Some(&mut dev));
+    let hist = y.histogram(2);
+    assert_eq!(hist.edges, vec![1.5, 2.0, 2.5]);
+    assert_eq!(hist.counts, vec![0, 2]);
+}
+
+#[test]
+fn named_functions_test() {
+    let mut dev = D::Naive::new();
+    let x =
+        F::input_on([2, 2], &[1.0, 2.0, 3.0, 4.0], Some(&mut dev)).with_dim_names(&["row", "col"]);
+    let y = F::sum_named(&x, "row");
+    assert_shape!(y, [1, 2]);
+    assert_eq!(y.to_vector(), vec![3.0, 7.0]);
+    assert_eq!(y.clone().shape().name(0), Some("row"));
+    let z = F::concat_named(&[&x, &x], "col");
+    assert_shape!(z, [2, 4]);
+    let w = F::sum_named(F::sqrt(&x) / &x - 1.0, "col");
+    assert_eq!(w.shape().name(0), Some("row"));
+}
+
+#[test]
+#[should_panic(expected = "dimension names of operands do not match")]
+fn elementwise_function_with_transposed_names() {
+    let mut dev = D::Naive::new();
+    let x = F::input_on([2, 2], &[1.0, 2.0, 3.0, 4.0], Some(&mut dev));
+    let a = x.clone().with_dim_names(&["hidden", "batch"]);
+    let b = x.with_dim_names(&["batch", "hidden"]);
+    let _ = a + b;
+}
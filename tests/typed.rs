@@ -0,0 +1,81 @@
+#[macro_use]
+extern crate primitiv;
+
+use primitiv::devices as D;
+use primitiv::node_functions as F;
+use primitiv::typed::{self, Dim, TypedNode, TypedShape};
+use primitiv::Graph;
+
+typed_dim!(Hidden = 3);
+typed_dim!(Input = 2);
+typed_dim!(pub Output = 1);
+
+#[test]
+fn typed_shape_test() {
+    assert_eq!(<(Hidden, Input)>::dims(), vec![3, 2]);
+    assert_eq!(<()>::dims(), vec![]);
+    assert_eq!(typed::Sum::<Hidden, Input>::SIZE, 5);
+}
+
+#[test]
+fn typed_node_test() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let w = TypedNode::<(Hidden, Input)>::new(F::input_into(
+        [3, 2],
+        &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+        Some(&mut dev),
+        Some(&mut g),
+    ));
+    let x = TypedNode::<(Input,)>::new(F::input_into(
+        ([2], 2),
+        &[1.0, 0.0, 0.0, 1.0],
+        Some(&mut dev),
+        Some(&mut g),
+    ));
+    let h: TypedNode<(Hidden,)> = typed::matmul(&w, &x);
+    assert_eq!(h.node().shape(), ([3], 2).into());
+    assert_eq!(h.node().to_vector(), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+
+    let y: TypedNode<(Hidden,)> = &h + &h;
+    assert_eq!(y.node().to_vector()[5], 12.0);
+
+    let wt: TypedNode<(Input, Hidden)> = typed::transpose(&w);
+    assert_eq!(wt.node().shape(), [2, 3].into());
+    let ww: TypedNode<(Hidden, typed::Sum<Input, Input>)> = typed::concat_cols(&w, &w);
+    assert_eq!(ww.node().shape(), [3, 4].into());
+    let wr: TypedNode<(typed::Sum<Hidden, Hidden>, Input)> = typed::concat_rows(&w, &w);
+    assert_eq!(wr.node().shape(), [6, 2].into());
+
+    let o: TypedNode<(Hidden, Input, Output)> = typed::reshape(&w);
+    assert_eq!(o.clone().into_node().shape(), [3, 2].into());
+    let flat = F::flatten(&o);
+    assert_eq!(flat.shape(), [6].into());
+}
+
+#[test]
+#[should_panic(expected = "shape mismatch")]
+fn typed_node_shape_mismatch() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let _ = TypedNode::<(Hidden,)>::new(F::input_into(
+        [2],
+        &[1.0, 2.0],
+        Some(&mut dev),
+        Some(&mut g),
+    ));
+}
+
+#[test]
+#[should_panic(expected = "can not reshape")]
+fn typed_reshape_volume_mismatch() {
+    let mut dev = D::Naive::new();
+    let mut g = Graph::new();
+    let x = TypedNode::<(Input,)>::new(F::input_into(
+        [2],
+        &[1.0, 2.0],
+        Some(&mut dev),
+        Some(&mut g),
+    ));
+    let _: TypedNode<(Hidden,)> = typed::reshape(&x);
+}